@@ -0,0 +1,524 @@
+use crate::{
+    database::{parse_tags, Day, DayShort, EntryKind, Hit, Priority, Project, Todo},
+    date::{self, today},
+    error::{self, Error},
+    export::{Export, Format},
+    import,
+    storage::Storage,
+};
+use rusqlite::Connection;
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+pub const USAGE: &str = "Usage: todo [--db <path> | --todo-txt <path>] [command]
+
+    todo                             open the interactive UI
+    todo add [--date D] [--due D] [--priority P] <text>
+                                     add a todo to today or schedule it on another day,
+                                     #words in the text become tags
+    todo add --project P <text>      add a todo to a project, creating the project if needed
+    todo list [--date D]             list the todos of a day, today by default
+    todo list --project P            list the todos of a project
+    todo projects                    list the projects
+    todo pull <id>                   move a project todo into today
+    todo due                         list unfinished todos that are due or overdue
+    todo done <id>                   mark a todo as completed
+    todo notes                       print today's notes
+    todo notes --append [text]       append a line to today's notes, read from stdin without text
+    todo stats                       print completed/total todos for every day
+    todo search <query>              search todos and notes of all days, matches in *stars*,
+                                     \"quoted phrases\" and prefix* queries work
+    todo export [--format F] [--from D] [--to D] [--output FILE]
+                                     export days and daily todos as markdown, json or csv,
+                                     the format defaults to the output extension or markdown
+    todo import [--format F] [--date D] [--dry-run] <file>
+                                     import a markdown checklist, todo.txt or json export,
+                                     undated entries go on --date, today by default,
+                                     todos already on their day are skipped, - reads stdin
+    todo help                        show this message
+
+The database is looked up in --db, $TODO_DB, the `database` key of the config
+file and finally the user data directory. With --todo-txt, $TODO_TXT or
+`storage = \"todo.txt\"` in the config todos are kept in a todo.txt file and
+the done.txt next to it instead.
+
+Dates are written as YYYY-MM-DD, today, tomorrow, yesterday or +N/-N days.";
+
+pub struct Args {
+    pub db: Option<PathBuf>,
+    pub todo_txt: Option<PathBuf>,
+    pub command: Option<Command>,
+}
+
+pub struct NewTodo {
+    text: String,
+    date: Option<String>,
+    due_date: Option<String>,
+    priority: Priority,
+    project: Option<String>,
+}
+
+pub struct ExportArgs {
+    format: Option<Format>,
+    from: Option<String>,
+    to: Option<String>,
+    output: Option<PathBuf>,
+}
+
+pub struct ImportArgs {
+    format: Option<import::Format>,
+    date: Option<String>,
+    dry_run: bool,
+    path: PathBuf,
+}
+
+pub enum Command {
+    Add(NewTodo),
+    List(Option<String>),
+    ListProject(String),
+    Projects,
+    Pull(i64),
+    Due,
+    Done(i64),
+    Notes,
+    AppendNotes(Option<String>),
+    Stats,
+    Search(String),
+    Export(ExportArgs),
+    Import(ImportArgs),
+    Help,
+}
+
+pub fn parse(args: &[String]) -> Result<Args, String> {
+    let mut db = None;
+    let mut todo_txt = None;
+    // Global options only come before the command, so the same flags can
+    // still be written in a todo text.
+    let mut args = args.iter().peekable();
+    let global = |arg: &&String| {
+        ["--db", "--todo-txt", "--"].contains(&arg.as_str())
+            || arg.starts_with("--db=")
+            || arg.starts_with("--todo-txt=")
+    };
+    while let Some(arg) = args.next_if(global) {
+        if arg == "--db" {
+            let path = args.next().ok_or("--db: missing database path")?;
+            db = Some(PathBuf::from(path));
+        } else if let Some(path) = arg.strip_prefix("--db=") {
+            db = Some(PathBuf::from(path));
+        } else if arg == "--todo-txt" {
+            let path = args.next().ok_or("--todo-txt: missing todo.txt path")?;
+            todo_txt = Some(PathBuf::from(path));
+        } else if let Some(path) = arg.strip_prefix("--todo-txt=") {
+            todo_txt = Some(PathBuf::from(path));
+        } else {
+            break;
+        }
+    }
+    let rest: Vec<String> = args.cloned().collect();
+    if db.is_some() && todo_txt.is_some() {
+        return Err(String::from("--db and --todo-txt cannot be combined"));
+    }
+    Ok(Args {
+        db,
+        todo_txt,
+        command: parse_command(&rest)?,
+    })
+}
+
+fn parse_command(args: &[String]) -> Result<Option<Command>, String> {
+    let (name, rest) = match args.split_first() {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    let command = match name.as_str() {
+        "add" => Command::Add(parse_new_todo(rest)?),
+        "list" => match rest {
+            [] => Command::List(None),
+            [flag, d] if flag == "--date" => {
+                Command::List(Some(date::parse(d).map_err(|e| format!("list: {}", e))?))
+            }
+            [flag, project] if flag == "--project" => Command::ListProject(project.clone()),
+            _ => return Err(String::from("list: expected [--date D] or --project P")),
+        },
+        "projects" => Command::Projects,
+        "pull" => match rest {
+            [id] => Command::Pull(
+                id.parse()
+                    .map_err(|_| format!("pull: invalid todo id '{}'", id))?,
+            ),
+            _ => return Err(String::from("pull: expected a project todo id")),
+        },
+        "due" => Command::Due,
+        "done" => match rest {
+            [id] => Command::Done(
+                id.parse()
+                    .map_err(|_| format!("done: invalid todo id '{}'", id))?,
+            ),
+            _ => return Err(String::from("done: expected a todo id")),
+        },
+        "notes" => match rest {
+            [] => Command::Notes,
+            [flag, text @ ..] if flag == "--append" => Command::AppendNotes(if text.is_empty() {
+                None
+            } else {
+                Some(text.join(" "))
+            }),
+            _ => return Err(String::from("notes: expected [--append [text]]")),
+        },
+        "stats" => Command::Stats,
+        "search" => match rest {
+            [] => return Err(String::from("search: missing query")),
+            words => Command::Search(words.join(" ")),
+        },
+        "export" => Command::Export(parse_export(rest)?),
+        "import" => Command::Import(parse_import(rest)?),
+        "help" | "-h" | "--help" => Command::Help,
+        other => return Err(format!("unknown command '{}'", other)),
+    };
+    Ok(Some(command))
+}
+
+fn parse_export(args: &[String]) -> Result<ExportArgs, String> {
+    let mut export = ExportArgs {
+        format: None,
+        from: None,
+        to: None,
+        output: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("export: {} expects a value", arg))?;
+        match arg.as_str() {
+            "--format" => {
+                export.format = Some(value.parse().map_err(|e| format!("export: {}", e))?)
+            }
+            "--from" => {
+                export.from = Some(date::parse(value).map_err(|e| format!("export: {}", e))?)
+            }
+            "--to" => export.to = Some(date::parse(value).map_err(|e| format!("export: {}", e))?),
+            "--output" => export.output = Some(PathBuf::from(value)),
+            _ => return Err(format!("export: unknown option '{}'", arg)),
+        }
+    }
+    Ok(export)
+}
+
+fn parse_import(args: &[String]) -> Result<ImportArgs, String> {
+    let mut format = None;
+    let mut date = None;
+    let mut dry_run = false;
+    let mut path = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--format" | "--date" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("import: {} expects a value", arg))?;
+                if arg == "--format" {
+                    format = Some(value.parse().map_err(|e| format!("import: {}", e))?);
+                } else {
+                    date = Some(date::parse(value).map_err(|e| format!("import: {}", e))?);
+                }
+            }
+            _ if path.is_none() => path = Some(PathBuf::from(arg)),
+            _ => return Err(format!("import: unexpected argument '{}'", arg)),
+        }
+    }
+    let path = path.ok_or("import: missing file to import")?;
+    Ok(ImportArgs {
+        format,
+        date,
+        dry_run,
+        path,
+    })
+}
+
+fn parse_new_todo(args: &[String]) -> Result<NewTodo, String> {
+    let mut todo = NewTodo {
+        text: String::new(),
+        date: None,
+        due_date: None,
+        priority: Priority::None,
+        project: None,
+    };
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--priority" {
+            let value = args
+                .next()
+                .ok_or("add: --priority expects high, medium or low")?;
+            todo.priority = value.parse().map_err(|e| format!("add: {}", e))?;
+            continue;
+        }
+        if arg == "--project" {
+            let value = args.next().ok_or("add: --project expects a project name")?;
+            todo.project = Some(value.clone());
+            continue;
+        }
+        let slot = match arg.as_str() {
+            "--date" => &mut todo.date,
+            "--due" => &mut todo.due_date,
+            _ => {
+                words.push(arg.as_str());
+                continue;
+            }
+        };
+        let value = args
+            .next()
+            .ok_or_else(|| format!("add: {} expects a date", arg))?;
+        *slot = Some(date::parse(value).map_err(|e| format!("add: {}", e))?);
+    }
+    todo.text = words.join(" ").trim().to_string();
+    if todo.text.is_empty() {
+        return Err(String::from("add: missing todo text"));
+    }
+    let dated = todo.date.is_some() || todo.due_date.is_some();
+    if todo.project.is_some() && (dated || todo.priority != Priority::None) {
+        return Err(String::from(
+            "add: --project cannot be combined with --date, --due or --priority",
+        ));
+    }
+    Ok(todo)
+}
+
+pub fn run(db: &Connection, storage: &Storage, command: Command) -> error::Result<()> {
+    match command {
+        Command::Add(NewTodo {
+            text,
+            project: Some(name),
+            ..
+        }) => {
+            storage.require_database("projects")?;
+            let mut project = match Project::find_by_name(db, &name)? {
+                Some(project) => project,
+                None => Project::new(db, &name)?,
+            };
+            project.add_todo(db, &text)?;
+            if let Some(todo) = project.todos.last() {
+                println!("{:>4} {}", todo.id, todo.get_text());
+            }
+        }
+        Command::Add(new) => {
+            let date = new.date.unwrap_or_else(today);
+            let mut day = Day::for_date(db, &date)?;
+            let (text, tags) = parse_tags(&new.text);
+            let mut todo = Todo::new(db, &text, day.id)?;
+            todo.set_tags(db, &tags)?;
+            if let Some(due_date) = &new.due_date {
+                todo.set_due_date(db, Some(due_date))?;
+            }
+            if new.priority != Priority::None {
+                todo.set_priority(db, new.priority)?;
+            }
+            println!("{:>4} {}", todo.id, todo.get_text());
+            day.add_todo(db, todo)?;
+        }
+        Command::List(date) => {
+            let date = date.unwrap_or_else(today);
+            match Day::find_by_date(db, &date)? {
+                Some(day) => {
+                    println!("{} {}/{}", day.date, day.done_todos, day.count_todos);
+                    for (index, todo) in day.todos.iter().enumerate() {
+                        let indent = "  ".repeat(day.depth(index));
+                        println!("{:>4} {}{}", todo.id, indent, todo.get_text());
+                    }
+                }
+                None => println!("{} has no todos", date),
+            }
+        }
+        Command::ListProject(name) => {
+            let project = Project::find_by_name(db, &name)?
+                .ok_or_else(|| Error::NotFound(format!("no project named '{}'", name)))?;
+            for todo in &project.todos {
+                println!("{:>4} {}", todo.id, todo.get_text());
+            }
+        }
+        Command::Projects => {
+            for project in Project::get_all(db)? {
+                let done = project.todos.iter().filter(|t| t.completed).count();
+                println!("{} {}/{}", project.name, done, project.todos.len());
+            }
+        }
+        Command::Pull(id) => {
+            let found = Project::get_all(db)?.into_iter().find_map(|project| {
+                let index = project.todos.iter().position(|t| t.id == id)?;
+                Some((project, index))
+            });
+            let (mut project, index) =
+                found.ok_or_else(|| Error::NotFound(format!("no project todo with id {}", id)))?;
+            let mut day = Day::get_or_create(db, &today())?;
+            project.pull(db, index, &mut day)?;
+            if let Some(todo) = day.todos.last() {
+                println!("{:>4} {}", todo.id, todo.get_text());
+            }
+        }
+        Command::Due => {
+            let today = today();
+            for todo in Todo::get_due(db, &today, None)? {
+                let marker = if todo.is_overdue(&today) { '!' } else { ' ' };
+                println!("{:>4}{}{}", todo.id, marker, todo.get_text());
+            }
+        }
+        Command::Done(id) => {
+            let mut todo = match Todo::get(db, id) {
+                Err(rusqlite::Error::QueryReturnedNoRows) => {
+                    return Err(Error::NotFound(format!("no todo with id {}", id)))
+                }
+                result => result?,
+            };
+            if !todo.completed {
+                todo.set_completed(db, true)?;
+                let mut day = Day::get(db, todo.day_id)?;
+                day.update_counts(db)?;
+            }
+            println!("{:>4} {}", todo.id, todo.get_text());
+        }
+        Command::Notes => {
+            if let Some(day) = Day::find_by_date(db, &today())? {
+                println!("{}", day.notes);
+            }
+        }
+        Command::AppendNotes(text) => {
+            storage.require_database("notes")?;
+            let text = match text {
+                Some(text) => text,
+                None => {
+                    let mut input = String::new();
+                    io::stdin().read_to_string(&mut input)?;
+                    input.trim_end().to_string()
+                }
+            };
+            let mut day = Day::get_or_create(db, &today())?;
+            if !day.notes.is_empty() && !day.notes.ends_with('\n') {
+                day.notes.push('\n');
+            }
+            day.notes.push_str(&text);
+            day.set_notes(db)?;
+        }
+        Command::Stats => {
+            for day in DayShort::get_all(db, None)? {
+                println!("{} {}", day.date, day.string);
+            }
+        }
+        Command::Search(query) => {
+            for hit in Hit::search(db, &query, 50)? {
+                let mark = match hit.kind {
+                    EntryKind::Todo { completed: true } => "[x]",
+                    EntryKind::Todo { completed: false } => "[ ]",
+                    EntryKind::Note => " ~ ",
+                };
+                let snippet: String = hit
+                    .snippet
+                    .iter()
+                    .map(|(part, matched)| match matched {
+                        true => format!("*{}*", part),
+                        false => part.clone(),
+                    })
+                    .collect();
+                println!("{} {} {}", hit.date, mark, snippet);
+            }
+        }
+        Command::Export(args) => {
+            let format = match (args.format, &args.output) {
+                (Some(format), _) => format,
+                (None, Some(path)) => Format::from_path(path)?,
+                (None, None) => Format::Markdown,
+            };
+            let export = Export::load(db, args.from.as_deref(), args.to.as_deref())?;
+            let output = export.render(format)?;
+            match &args.output {
+                Some(path) => fs::write(path, output)?,
+                None => print!("{}", output),
+            }
+        }
+        Command::Import(args) => {
+            let format = match args.format {
+                Some(format) => format,
+                None if args.path.as_os_str() == "-" => import::Format::Markdown,
+                None => import::Format::from_path(&args.path)?,
+            };
+            let input = if args.path.as_os_str() == "-" {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                input
+            } else {
+                fs::read_to_string(&args.path).map_err(|e| {
+                    io::Error::new(e.kind(), format!("{}: {}", args.path.display(), e))
+                })?
+            };
+            let date = args.date.unwrap_or_else(today);
+            let export = import::parse(&input, format, &date)?;
+            if export.days.iter().any(|day| !day.notes.trim().is_empty()) {
+                storage.require_database("notes")?;
+            }
+            if !export.daily_todos.is_empty() {
+                storage.require_database("daily todos")?;
+            }
+            if export
+                .days
+                .iter()
+                .flat_map(|day| &day.todos)
+                .any(|todo| todo.depth > 0)
+            {
+                storage.require_database("sub-tasks")?;
+            }
+            // A dry run goes through the same steps and rolls them back.
+            let tx = db.unchecked_transaction()?;
+            let report = import::apply(&tx, &export)?;
+            for line in &report.lines {
+                println!("{}", line);
+            }
+            let verb = if args.dry_run {
+                "would import"
+            } else {
+                tx.commit()?;
+                "imported"
+            };
+            println!(
+                "{} {}, skipped {} already there",
+                verb, report.added, report.duplicates
+            );
+        }
+        Command::Help => println!("{}", USAGE),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn global_options_only_come_before_the_command() {
+        let parsed = parse(&args("--db=a.db add document the --db flag")).unwrap();
+        assert_eq!(parsed.db, Some(PathBuf::from("a.db")));
+        match parsed.command {
+            Some(Command::Add(todo)) => assert_eq!(todo.text, "document the --db flag"),
+            _ => panic!("expected add"),
+        }
+
+        let parsed = parse(&args("--todo-txt t.txt -- list")).unwrap();
+        assert_eq!(parsed.todo_txt, Some(PathBuf::from("t.txt")));
+        assert!(matches!(parsed.command, Some(Command::List(None))));
+
+        assert!(matches!(
+            parse(&args("--help")).unwrap().command,
+            Some(Command::Help)
+        ));
+        assert!(parse(&args("--db a.db --todo-txt t.txt")).is_err());
+        assert!(parse(&args("list --db a.db")).is_err());
+    }
+}
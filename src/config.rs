@@ -0,0 +1,439 @@
+use crate::error::{self, Error};
+use crossterm::event::KeyCode;
+use serde::Deserialize;
+use std::{
+    convert::TryFrom,
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tui::style::Color;
+
+const DATABASE_FILE: &str = "database.sqlite";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Toggle,
+    Delete,
+    New,
+    Edit,
+    OpenEditor,
+    Due,
+    Repeat,
+    Indent,
+    Outdent,
+    Fold,
+    Priority,
+    Sort,
+    Tags,
+    Filter,
+    Search,
+    Undo,
+    Trash,
+    Export,
+    DailyTodos,
+    Projects,
+    Stats,
+    Quit,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keys {
+    pub up: char,
+    pub down: char,
+    pub left: char,
+    pub right: char,
+    pub toggle: char,
+    pub delete: char,
+    pub new: char,
+    pub edit: char,
+    pub open_editor: char,
+    pub due: char,
+    pub repeat: char,
+    pub indent: char,
+    pub outdent: char,
+    pub fold: char,
+    pub priority: char,
+    pub sort: char,
+    pub tags: char,
+    pub filter: char,
+    pub search: char,
+    pub undo: char,
+    pub trash: char,
+    pub export: char,
+    pub daily_todos: char,
+    pub projects: char,
+    pub stats: char,
+    pub quit: char,
+}
+
+impl Default for Keys {
+    fn default() -> Self {
+        Self {
+            up: 'k',
+            down: 'j',
+            left: 'h',
+            right: 'l',
+            toggle: 'x',
+            delete: 'd',
+            new: 'n',
+            edit: 'e',
+            open_editor: 'o',
+            due: 'w',
+            repeat: 'r',
+            indent: '>',
+            outdent: '<',
+            fold: 'z',
+            priority: 'p',
+            sort: 'v',
+            tags: 'g',
+            filter: 'f',
+            search: '/',
+            undo: 'u',
+            trash: 'c',
+            export: 'a',
+            daily_todos: 't',
+            projects: 'b',
+            stats: 's',
+            quit: 'q',
+        }
+    }
+}
+
+impl Keys {
+    fn bindings(&self) -> [(Action, char, &'static str); 26] {
+        [
+            (Action::Up, self.up, "up"),
+            (Action::Down, self.down, "down"),
+            (Action::Left, self.left, "left"),
+            (Action::Right, self.right, "right"),
+            (Action::Toggle, self.toggle, "toggle"),
+            (Action::Delete, self.delete, "delete"),
+            (Action::New, self.new, "new"),
+            (Action::Edit, self.edit, "edit"),
+            (Action::OpenEditor, self.open_editor, "open_editor"),
+            (Action::Due, self.due, "due"),
+            (Action::Repeat, self.repeat, "repeat"),
+            (Action::Indent, self.indent, "indent"),
+            (Action::Outdent, self.outdent, "outdent"),
+            (Action::Fold, self.fold, "fold"),
+            (Action::Priority, self.priority, "priority"),
+            (Action::Sort, self.sort, "sort"),
+            (Action::Tags, self.tags, "tags"),
+            (Action::Filter, self.filter, "filter"),
+            (Action::Search, self.search, "search"),
+            (Action::Undo, self.undo, "undo"),
+            (Action::Trash, self.trash, "trash"),
+            (Action::Export, self.export, "export"),
+            (Action::DailyTodos, self.daily_todos, "daily_todos"),
+            (Action::Projects, self.projects, "projects"),
+            (Action::Stats, self.stats, "stats"),
+            (Action::Quit, self.quit, "quit"),
+        ]
+    }
+
+    // Letters are matched case-insensitively so that Shift can modify an action.
+    // Tab and Shift+Tab always indent and outdent.
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        match code {
+            KeyCode::Up => Some(Action::Up),
+            KeyCode::Down => Some(Action::Down),
+            KeyCode::Left => Some(Action::Left),
+            KeyCode::Right => Some(Action::Right),
+            KeyCode::Tab => Some(Action::Indent),
+            KeyCode::BackTab => Some(Action::Outdent),
+            KeyCode::Char(c) => self
+                .bindings()
+                .iter()
+                .find(|(_, key, _)| key.eq_ignore_ascii_case(&c))
+                .map(|(action, _, _)| *action),
+            _ => None,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let bindings = self.bindings();
+        for (i, (_, key, name)) in bindings.iter().enumerate() {
+            if key.is_whitespace() || key.is_control() {
+                return Err(format!("keys.{}: '{}' cannot be bound", name, key));
+            }
+            if let Some((_, _, other)) = bindings[..i]
+                .iter()
+                .find(|(_, k, _)| k.eq_ignore_ascii_case(key))
+            {
+                return Err(format!(
+                    "keys.{} and keys.{} are both bound to '{}'",
+                    other, name, key
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct ThemeColor(pub Color);
+
+impl TryFrom<String> for ThemeColor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let color = match value.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" | "grey" => Color::Gray,
+            "darkgray" | "darkgrey" => Color::DarkGray,
+            "lightred" => Color::LightRed,
+            "lightgreen" => Color::LightGreen,
+            "lightyellow" => Color::LightYellow,
+            "lightblue" => Color::LightBlue,
+            "lightmagenta" => Color::LightMagenta,
+            "lightcyan" => Color::LightCyan,
+            "white" => Color::White,
+            hex if hex.starts_with('#') && hex.len() == 7 => {
+                let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+                match (channel(1), channel(3), channel(5)) {
+                    (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                    _ => return Err(format!("invalid colour '{}'", value)),
+                }
+            }
+            index => Color::Indexed(index.parse().map_err(|_| {
+                format!(
+                    "invalid colour '{}', expected a name, #rrggbb or 0-255",
+                    value
+                )
+            })?),
+        };
+        Ok(Self(color))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub active: ThemeColor,
+    pub inactive: ThemeColor,
+    pub text: ThemeColor,
+    pub error: ThemeColor,
+    pub high: ThemeColor,
+    pub medium: ThemeColor,
+    pub low: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            active: ThemeColor(Color::Yellow),
+            inactive: ThemeColor(Color::White),
+            text: ThemeColor(Color::White),
+            error: ThemeColor(Color::Red),
+            high: ThemeColor(Color::LightRed),
+            medium: ThemeColor(Color::LightCyan),
+            low: ThemeColor(Color::Gray),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layout {
+    pub todos_width: u16,
+    pub popup_width: u16,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            todos_width: 30,
+            popup_width: 60,
+        }
+    }
+}
+
+impl Layout {
+    fn validate(&self) -> Result<(), String> {
+        for (name, value) in [
+            ("todos_width", self.todos_width),
+            ("popup_width", self.popup_width),
+        ] {
+            if !(10..=90).contains(&value) {
+                return Err(format!(
+                    "layout.{}: {} is out of range, expected a percentage between 10 and 90",
+                    name, value
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What happens to yesterday's unfinished todos when a new day starts.
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CarryOver {
+    #[default]
+    Carry,
+    Ask,
+    Leave,
+}
+
+/// Whether the app moves to a new day on its own once the date changes.
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DayRollover {
+    #[default]
+    Auto,
+    Ask,
+    Off,
+}
+
+/// Where todos are kept, see `storage::Storage`.
+#[derive(Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum Backend {
+    #[default]
+    #[serde(rename = "sqlite")]
+    Sqlite,
+    #[serde(rename = "todo.txt")]
+    TodoTxt,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub storage: Backend,
+    pub database: Option<PathBuf>,
+    pub todo_txt: Option<PathBuf>,
+    pub carry_over: CarryOver,
+    pub day_rollover: DayRollover,
+    /// Days deleted todos stay in the trash, 0 keeps them until purged.
+    pub trash_days: u32,
+    pub keys: Keys,
+    pub theme: Theme,
+    pub layout: Layout,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            storage: Backend::default(),
+            database: None,
+            todo_txt: None,
+            carry_over: CarryOver::default(),
+            day_rollover: DayRollover::default(),
+            trash_days: 30,
+            keys: Keys::default(),
+            theme: Theme::default(),
+            layout: Layout::default(),
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("todo").join("config.toml"))
+    }
+
+    pub fn load() -> error::Result<Self> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::load_from(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> error::Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("cannot read config {}: {}", path.display(), e)))?;
+        let config: Self = toml::from_str(&content)
+            .map_err(|e| Error::Config(format!("invalid config {}: {}", path.display(), e)))?;
+        config
+            .validate()
+            .map_err(|e| Error::Config(format!("invalid config {}: {}", path.display(), e)))?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.keys.validate()?;
+        self.layout.validate()
+    }
+}
+
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn default_database_path() -> io::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join("todo").join(DATABASE_FILE))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot find a data directory"))
+}
+
+// Older versions kept the database next to the executable.
+fn legacy_database_path() -> io::Result<PathBuf> {
+    let mut path = std::env::current_exe()?;
+    path.pop();
+    path.push(DATABASE_FILE);
+    Ok(path)
+}
+
+fn migrate_legacy_database(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    let legacy = legacy_database_path()?;
+    if legacy.exists() {
+        let action = if fs::rename(&legacy, path).is_ok() {
+            "moved"
+        } else {
+            fs::copy(&legacy, path)?;
+            "copied"
+        };
+        eprintln!(
+            "todo: {} database from {} to {}",
+            action,
+            legacy.display(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+fn create_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+// Resolution order: --db flag, TODO_DB, config file, then the data directory.
+pub fn database_path(flag: Option<PathBuf>, config: &Config) -> io::Result<PathBuf> {
+    let explicit = flag
+        .or_else(|| std::env::var_os("TODO_DB").map(PathBuf::from))
+        .or_else(|| config.database.clone());
+    let path = match explicit {
+        Some(path) => {
+            let path = expand_home(&path);
+            create_parent_dir(&path)?;
+            path
+        }
+        None => {
+            let path = default_database_path()?;
+            create_parent_dir(&path)?;
+            migrate_legacy_database(&path)?;
+            path
+        }
+    };
+    Ok(path)
+}
@@ -0,0 +1,357 @@
+use rusqlite::{
+    params, params_from_iter, types::Value, Connection, Result, Transaction, TransactionBehavior,
+};
+
+/// Changes kept for undo, older ones are dropped.
+const LIMIT: usize = 100;
+
+/// Part of the database a change touches.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scope {
+    /// The todos of a day with their tags, and the day's notes.
+    Day(i64),
+    ProjectTodo(i64),
+}
+
+impl Scope {
+    // Tables with the rows of this scope, in the order they are inserted.
+    fn tables(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Scope::Day(_) => &[
+                ("todos", "day_id = ?1"),
+                (
+                    "todo_tags",
+                    "todo_id IN (SELECT id FROM todos WHERE day_id = ?1)",
+                ),
+            ],
+            Scope::ProjectTodo(_) => &[("project_todos", "id = ?1")],
+        }
+    }
+
+    fn id(self) -> i64 {
+        match self {
+            Scope::Day(id) | Scope::ProjectTodo(id) => id,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+struct Rows {
+    table: &'static str,
+    columns: Vec<String>,
+    values: Vec<Vec<Value>>,
+}
+
+#[derive(PartialEq)]
+struct DayRow {
+    id: i64,
+    notes: String,
+    count_todos: i64,
+    done_todos: i64,
+}
+
+/// The rows of some scopes at one point in time. Restoring it replaces what
+/// the scopes hold now, ids included, so references between rows stay valid.
+#[derive(PartialEq)]
+pub struct Snapshot {
+    scopes: Vec<Scope>,
+    rows: Vec<Rows>,
+    days: Vec<DayRow>,
+}
+
+impl Snapshot {
+    pub fn take(db: &Connection, scopes: &[Scope]) -> Result<Self> {
+        let mut rows = Vec::new();
+        let mut days = Vec::new();
+        for scope in scopes {
+            for (table, filter) in scope.tables() {
+                let mut stmt = db.prepare(&format!("SELECT * FROM {} WHERE {}", table, filter))?;
+                let columns: Vec<String> =
+                    stmt.column_names().into_iter().map(String::from).collect();
+                let values = stmt
+                    .query_map([scope.id()], |r| {
+                        (0..columns.len()).map(|i| r.get::<_, Value>(i)).collect()
+                    })?
+                    .collect::<Result<Vec<Vec<Value>>>>()?;
+                rows.push(Rows {
+                    table,
+                    columns,
+                    values,
+                });
+            }
+            if let Scope::Day(id) = scope {
+                days.push(db.query_row(
+                    "SELECT notes, count_todos, done_todos FROM days WHERE id = ?1",
+                    [id],
+                    |r| {
+                        Ok(DayRow {
+                            id: *id,
+                            notes: r.get(0)?,
+                            count_todos: r.get(1)?,
+                            done_todos: r.get(2)?,
+                        })
+                    },
+                )?);
+            }
+        }
+        Ok(Self {
+            scopes: scopes.to_vec(),
+            rows,
+            days,
+        })
+    }
+
+    /// Puts the scopes back into this state in a single transaction, as long
+    /// as they still hold `current`. Returns false without touching anything
+    /// when something else, such as the command line, changed them since.
+    fn restore(&self, db: &Connection, current: &Snapshot) -> Result<bool> {
+        let tx = Transaction::new_unchecked(db, TransactionBehavior::Immediate)?;
+        if Snapshot::take(&tx, &current.scopes)? != *current {
+            return Ok(false);
+        }
+        // Tables are deleted from in reverse so that subqueries on the todos
+        // of a day still see them.
+        for scope in &self.scopes {
+            for (table, filter) in scope.tables().iter().rev() {
+                tx.execute(
+                    &format!("DELETE FROM {} WHERE {}", table, filter),
+                    [scope.id()],
+                )?;
+            }
+        }
+        for rows in &self.rows {
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                rows.table,
+                rows.columns.join(", "),
+                vec!["?"; rows.columns.len()].join(", ")
+            );
+            for values in &rows.values {
+                tx.execute(&sql, params_from_iter(values))?;
+            }
+        }
+        for day in &self.days {
+            tx.execute(
+                "UPDATE days SET notes = ?1, count_todos = ?2, done_todos = ?3 WHERE id = ?4",
+                params![day.notes, day.count_todos, day.done_todos, day.id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+}
+
+struct Change {
+    label: &'static str,
+    before: Snapshot,
+    after: Snapshot,
+}
+
+/// What undo or redo did, with the label of the change.
+pub enum Step {
+    Done(&'static str),
+    Nothing,
+    /// The rows were changed outside this session, the history is dropped.
+    Conflict(&'static str),
+}
+
+/// Undo and redo stacks of the changes made in this session.
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+impl History {
+    pub fn push(&mut self, label: &'static str, before: Snapshot, after: Snapshot) {
+        if before == after {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push(Change {
+            label,
+            before,
+            after,
+        });
+        if self.undo.len() > LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Reverts the last change.
+    pub fn undo(&mut self, db: &Connection) -> Result<Step> {
+        let change = match self.undo.pop() {
+            Some(change) => change,
+            None => return Ok(Step::Nothing),
+        };
+        match change.before.restore(db, &change.after) {
+            Ok(true) => {
+                let label = change.label;
+                self.redo.push(change);
+                Ok(Step::Done(label))
+            }
+            Ok(false) => {
+                self.clear();
+                Ok(Step::Conflict(change.label))
+            }
+            Err(e) => {
+                self.undo.push(change);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn redo(&mut self, db: &Connection) -> Result<Step> {
+        let change = match self.redo.pop() {
+            Some(change) => change,
+            None => return Ok(Step::Nothing),
+        };
+        match change.after.restore(db, &change.before) {
+            Ok(true) => {
+                let label = change.label;
+                self.undo.push(change);
+                Ok(Step::Done(label))
+            }
+            Ok(false) => {
+                self.clear();
+                Ok(Step::Conflict(change.label))
+            }
+            Err(e) => {
+                self.redo.push(change);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{init_connection, Day, Project, Todo};
+    use std::path::Path;
+
+    fn open() -> (Connection, Day) {
+        let db = init_connection(Path::new(":memory:")).unwrap();
+        let day = Day::plan(&db, "2022-09-01").unwrap();
+        (db, day)
+    }
+
+    /// Records `change` the way the app does.
+    fn record(
+        db: &Connection,
+        history: &mut History,
+        label: &'static str,
+        scopes: &[Scope],
+        change: impl FnOnce(),
+    ) {
+        let before = Snapshot::take(db, scopes).unwrap();
+        change();
+        history.push(label, before, Snapshot::take(db, scopes).unwrap());
+    }
+
+    fn state(db: &Connection, day: &Day) -> (String, Vec<String>) {
+        let day = Day::get(db, day.id).unwrap();
+        let todos = day.todos.iter().map(|todo| todo.get_text()).collect();
+        (day.notes, todos)
+    }
+
+    #[test]
+    fn undo_and_redo_todos_and_notes() {
+        let (db, mut day) = open();
+        let scopes = [Scope::Day(day.id)];
+        let mut history = History::default();
+        record(&db, &mut history, "add", &scopes, || {
+            let mut todo = Todo::new(&db, "buy milk", day.id).unwrap();
+            todo.set_tags(&db, &[String::from("errands")]).unwrap();
+            day.add_todo(&db, todo).unwrap();
+        });
+        record(&db, &mut history, "edit", &scopes, || {
+            day.notes = String::from("went well");
+            day.set_notes(&db).unwrap();
+            day.todos[0].toggle(&db).unwrap();
+            day.update_counts(&db).unwrap();
+        });
+        let added = (String::new(), vec![String::from("[ ] buy milk #errands")]);
+        let edited = (
+            String::from("went well"),
+            vec![String::from("[x] buy milk #errands")],
+        );
+        assert_eq!(state(&db, &day), edited);
+
+        assert!(matches!(history.undo(&db).unwrap(), Step::Done("edit")));
+        assert_eq!(state(&db, &day), added);
+        assert!(matches!(history.undo(&db).unwrap(), Step::Done("add")));
+        assert_eq!(state(&db, &day), (String::new(), Vec::new()));
+        assert!(matches!(history.undo(&db).unwrap(), Step::Nothing));
+
+        assert!(matches!(history.redo(&db).unwrap(), Step::Done("add")));
+        assert_eq!(state(&db, &day), added);
+        assert!(matches!(history.redo(&db).unwrap(), Step::Done("edit")));
+        assert_eq!(state(&db, &day), edited);
+        assert!(matches!(history.redo(&db).unwrap(), Step::Nothing));
+        let day = Day::get(&db, day.id).unwrap();
+        assert_eq!((day.count_todos, day.done_todos), (1, 1));
+    }
+
+    #[test]
+    fn undo_a_project_todo() {
+        let db = init_connection(Path::new(":memory:")).unwrap();
+        let mut project = Project::new(&db, "home").unwrap();
+        project.add_todo(&db, "paint").unwrap();
+        let scopes = [Scope::ProjectTodo(project.todos[0].id)];
+        let mut history = History::default();
+        record(&db, &mut history, "edit", &scopes, || {
+            project.todos[0].set_text(&db, "paint the fence").unwrap();
+        });
+        assert!(matches!(history.undo(&db).unwrap(), Step::Done("edit")));
+        let project = Project::find_by_name(&db, "home").unwrap().unwrap();
+        assert_eq!(project.todos[0].text, "paint");
+    }
+
+    #[test]
+    fn refuse_to_undo_over_outside_changes() {
+        let (db, mut day) = open();
+        let scopes = [Scope::Day(day.id)];
+        let mut history = History::default();
+        record(&db, &mut history, "add", &scopes, || {
+            let todo = Todo::new(&db, "mine", day.id).unwrap();
+            day.add_todo(&db, todo).unwrap();
+        });
+        Todo::new(&db, "from the command line", day.id).unwrap();
+
+        assert!(matches!(history.undo(&db).unwrap(), Step::Conflict("add")));
+        let texts: Vec<String> = Todo::get_all(&db, day.id)
+            .unwrap()
+            .into_iter()
+            .map(|todo| todo.text)
+            .collect();
+        assert_eq!(texts, ["mine", "from the command line"]);
+        assert!(matches!(history.undo(&db).unwrap(), Step::Nothing));
+    }
+
+    #[test]
+    fn refuse_to_redo_over_outside_changes() {
+        let (db, mut day) = open();
+        let scopes = [Scope::Day(day.id)];
+        let mut history = History::default();
+        record(&db, &mut history, "notes", &scopes, || {
+            day.notes = String::from("mine");
+            day.set_notes(&db).unwrap();
+        });
+        assert!(matches!(history.undo(&db).unwrap(), Step::Done("notes")));
+        day.notes = String::from("from the editor");
+        day.set_notes(&db).unwrap();
+
+        assert!(matches!(
+            history.redo(&db).unwrap(),
+            Step::Conflict("notes")
+        ));
+        assert_eq!(state(&db, &day).0, "from the editor");
+        assert!(matches!(history.redo(&db).unwrap(), Step::Nothing));
+    }
+}
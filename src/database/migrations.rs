@@ -0,0 +1,212 @@
+use rusqlite::{ffi, Connection, Error, Result};
+
+/// Schema upgrade steps in order. `MIGRATIONS[n]` brings a database from
+/// version `n` to `n + 1`, so new steps are only ever appended.
+const MIGRATIONS: &[&str] = &[
+    // 1: initial schema, matches databases created before versioning
+    "CREATE TABLE IF NOT EXISTS todos (
+        id INTEGER PRIMARY KEY,
+        position INTEGER,
+        day_id INTEGER,
+        text TEXT NOT NULL,
+        completed INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS days (
+        id INTEGER PRIMARY KEY,
+        count_todos INTEGER NOT NULL,
+        done_todos INTEGER NOT NULL,
+        notes TEXT NOT NULL,
+        date TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS daily_todos (
+        id INTEGER PRIMARY KEY,
+        position INTEGER,
+        text TEXT NOT NULL
+    );",
+    // 2: due dates on todos, days created ahead of time for scheduled todos
+    "ALTER TABLE todos ADD COLUMN due_date TEXT;
+    ALTER TABLE days ADD COLUMN planned INTEGER NOT NULL DEFAULT 0;",
+    // 3: carry-over of unfinished todos, existing days count as rolled over
+    "ALTER TABLE todos ADD COLUMN daily_todo_id INTEGER;
+    ALTER TABLE todos ADD COLUMN carried_from INTEGER;
+    ALTER TABLE todos ADD COLUMN deferred_since TEXT;
+    ALTER TABLE days ADD COLUMN rolled_over INTEGER NOT NULL DEFAULT 1;",
+    // 4: recurrence rules on daily todos
+    "ALTER TABLE daily_todos ADD COLUMN recurrence TEXT NOT NULL DEFAULT 'daily';",
+    // 5: sub-tasks
+    "ALTER TABLE todos ADD COLUMN parent_id INTEGER;
+    ALTER TABLE todos ADD COLUMN collapsed INTEGER NOT NULL DEFAULT 0;",
+    // 6: priorities, 0 is none and 3 is high
+    "ALTER TABLE todos ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;",
+    // 7: tags
+    "CREATE TABLE tags (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE todo_tags (
+        todo_id INTEGER NOT NULL,
+        tag_id INTEGER NOT NULL,
+        PRIMARY KEY (todo_id, tag_id)
+    );",
+    // 8: projects, lists of todos that are not tied to a day
+    "CREATE TABLE projects (
+        id INTEGER PRIMARY KEY,
+        position INTEGER,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE project_todos (
+        id INTEGER PRIMARY KEY,
+        project_id INTEGER NOT NULL,
+        position INTEGER,
+        text TEXT NOT NULL,
+        completed INTEGER NOT NULL DEFAULT 0
+    );",
+    // 9: full-text search over todo texts and day notes, kept in sync by triggers
+    "CREATE VIRTUAL TABLE todos_fts USING fts5(
+        text, content='todos', content_rowid='id', tokenize='unicode61 remove_diacritics 2'
+    );
+    CREATE VIRTUAL TABLE notes_fts USING fts5(
+        notes, content='days', content_rowid='id', tokenize='unicode61 remove_diacritics 2'
+    );
+    CREATE TRIGGER todos_fts_insert AFTER INSERT ON todos BEGIN
+        INSERT INTO todos_fts (rowid, text) VALUES (new.id, new.text);
+    END;
+    CREATE TRIGGER todos_fts_delete AFTER DELETE ON todos BEGIN
+        INSERT INTO todos_fts (todos_fts, rowid, text) VALUES ('delete', old.id, old.text);
+    END;
+    CREATE TRIGGER todos_fts_update AFTER UPDATE OF text ON todos BEGIN
+        INSERT INTO todos_fts (todos_fts, rowid, text) VALUES ('delete', old.id, old.text);
+        INSERT INTO todos_fts (rowid, text) VALUES (new.id, new.text);
+    END;
+    CREATE TRIGGER notes_fts_insert AFTER INSERT ON days BEGIN
+        INSERT INTO notes_fts (rowid, notes) VALUES (new.id, new.notes);
+    END;
+    CREATE TRIGGER notes_fts_delete AFTER DELETE ON days BEGIN
+        INSERT INTO notes_fts (notes_fts, rowid, notes) VALUES ('delete', old.id, old.notes);
+    END;
+    CREATE TRIGGER notes_fts_update AFTER UPDATE OF notes ON days BEGIN
+        INSERT INTO notes_fts (notes_fts, rowid, notes) VALUES ('delete', old.id, old.notes);
+        INSERT INTO notes_fts (rowid, notes) VALUES (new.id, new.notes);
+    END;
+    INSERT INTO todos_fts (todos_fts) VALUES ('rebuild');
+    INSERT INTO notes_fts (notes_fts) VALUES ('rebuild');",
+    // 10: soft delete, deleted rows stay in the trash until purged
+    "ALTER TABLE todos ADD COLUMN deleted_at TEXT;
+    ALTER TABLE daily_todos ADD COLUMN deleted_at TEXT;",
+    // 11: look up the copies carried over from a todo
+    "CREATE INDEX IF NOT EXISTS todos_carried_from ON todos (carried_from);",
+    // 12: deleted todos point at the todo whose deletion took them along,
+    // rows already in the trash are grouped like before, by timestamp
+    "ALTER TABLE todos ADD COLUMN deleted_with INTEGER;
+    UPDATE todos SET deleted_with = (
+        WITH RECURSIVE subtree (root, id, deleted_at) AS (
+            SELECT id, id, deleted_at FROM todos WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT subtree.root, todos.id, todos.deleted_at FROM todos
+            JOIN subtree ON todos.parent_id = subtree.id AND todos.deleted_at = subtree.deleted_at
+        )
+        SELECT root FROM subtree AS roots WHERE roots.id = todos.id
+            AND root NOT IN (SELECT id FROM subtree WHERE root != id)
+    ) WHERE deleted_at IS NOT NULL;",
+];
+
+pub fn latest_version() -> usize {
+    MIGRATIONS.len()
+}
+
+pub fn schema_version(conn: &Connection) -> Result<usize> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    Ok(version as usize)
+}
+
+pub fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    if current > latest_version() {
+        return Err(Error::SqliteFailure(
+            ffi::Error::new(ffi::SQLITE_CANTOPEN),
+            Some(format!(
+                "database schema version {} is newer than the supported version {}",
+                current,
+                latest_version()
+            )),
+        ));
+    }
+    for (index, step) in MIGRATIONS.iter().enumerate().skip(current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(step)?;
+        tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upgrades_a_database_from_before_versioning() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE todos (
+                id INTEGER PRIMARY KEY,
+                position INTEGER,
+                day_id INTEGER,
+                text TEXT NOT NULL,
+                completed INTEGER NOT NULL
+            );
+            CREATE TABLE days (
+                id INTEGER PRIMARY KEY,
+                count_todos INTEGER NOT NULL,
+                done_todos INTEGER NOT NULL,
+                notes TEXT NOT NULL,
+                date TEXT NOT NULL
+            );
+            CREATE TABLE daily_todos (
+                id INTEGER PRIMARY KEY,
+                position INTEGER,
+                text TEXT NOT NULL
+            );
+            INSERT INTO days VALUES (1, 1, 0, 'old notes', '2022-09-01');
+            INSERT INTO todos VALUES (1, 1, 1, 'old todo', 0);",
+        )
+        .unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        let (text, priority, rolled_over): (String, i64, bool) = conn
+            .query_row(
+                "SELECT todos.text, todos.priority, days.rolled_over
+                FROM todos JOIN days ON days.id = todos.day_id",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (text.as_str(), priority, rolled_over),
+            ("old todo", 0, true)
+        );
+        let found: i64 = conn
+            .query_row(
+                "SELECT rowid FROM todos_fts WHERE todos_fts MATCH 'old'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(found, 1);
+
+        migrate(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn refuses_a_newer_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let newer = (latest_version() + 1) as i64;
+        conn.pragma_update(None, "user_version", newer).unwrap();
+        let error = migrate(&mut conn).unwrap_err();
+        assert!(error.to_string().contains("is newer than"), "{}", error);
+        assert_eq!(schema_version(&conn).unwrap(), latest_version() + 1);
+    }
+}
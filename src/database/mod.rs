@@ -0,0 +1,878 @@
+mod history;
+mod migrations;
+mod projects;
+mod search;
+mod tags;
+mod trash;
+mod tree;
+
+pub use history::{History, Scope, Snapshot, Step};
+pub use projects::{Project, ProjectTodo};
+pub use search::{Entry, EntryKind, Hit};
+pub use tags::{parse_tags, Tag};
+pub use trash::{purge_expired, TrashItem, TrashKind};
+
+use crate::{date, recurrence::Recurrence};
+use chrono::NaiveDate;
+use rusqlite::{
+    params, params_from_iter,
+    types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef},
+    Connection, OptionalExtension, Result, Row,
+};
+use std::{fmt, path::Path, str::FromStr, time::Duration};
+
+const TODO_COLUMNS: &str = "id, day_id, position, text, completed, due_date, daily_todo_id, \
+    deferred_since, parent_id, collapsed, priority, \
+    (SELECT group_concat(name, ' ') FROM tags \
+    WHERE id IN (SELECT tag_id FROM todo_tags WHERE todo_id = todos.id)), \
+    EXISTS (SELECT 1 FROM todos AS copies WHERE copies.carried_from = todos.id)";
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    const ALL: [Priority; 4] = [
+        Priority::None,
+        Priority::Low,
+        Priority::Medium,
+        Priority::High,
+    ];
+
+    pub fn marker(self) -> &'static str {
+        match self {
+            Priority::None => "",
+            Priority::Low => "! ",
+            Priority::Medium => "!! ",
+            Priority::High => "!!! ",
+        }
+    }
+
+    /// The next higher priority, wrapping from high back to none.
+    pub fn raise(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    pub fn lower(self) -> Self {
+        Self::ALL[(self as usize + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Priority::None => "none",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "0" => Ok(Priority::None),
+            "low" | "1" => Ok(Priority::Low),
+            "medium" | "2" => Ok(Priority::Medium),
+            "high" | "3" => Ok(Priority::High),
+            _ => Err(format!(
+                "invalid priority '{}', expected high, medium, low or none",
+                s
+            )),
+        }
+    }
+}
+
+impl FromSql for Priority {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        let value = value.as_i64()?;
+        Self::ALL
+            .get(value as usize)
+            .copied()
+            .ok_or(FromSqlError::OutOfRange(value))
+    }
+}
+
+impl ToSql for Priority {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(*self as i64))
+    }
+}
+
+pub struct Todo {
+    pub id: i64,
+    #[allow(dead_code)]
+    pub position: i64,
+    pub day_id: i64,
+    pub text: String,
+    pub completed: bool,
+    pub due_date: Option<String>,
+    pub daily_todo_id: Option<i64>,
+    pub deferred_since: Option<String>,
+    pub parent_id: Option<i64>,
+    pub collapsed: bool,
+    pub priority: Priority,
+    pub tags: Vec<String>,
+    /// Copied into a later day, which is where the todo lives on.
+    pub carried: bool,
+}
+
+impl Todo {
+    pub fn new(db: &Connection, text: &str, day_id: i64) -> Result<Self> {
+        db.execute(
+            "INSERT INTO todos (text, completed, day_id) VALUES (?1, 0, ?2)",
+            [text, day_id.to_string().as_str()],
+        )?;
+        let last_id = db.last_insert_rowid();
+        db.execute(
+            "UPDATE todos SET position = ?1 WHERE id = ?2",
+            [&last_id, &last_id],
+        )?;
+        Ok(Self {
+            id: db.last_insert_rowid(),
+            day_id,
+            position: last_id,
+            text: text.to_string(),
+            completed: false,
+            due_date: None,
+            daily_todo_id: None,
+            deferred_since: None,
+            parent_id: None,
+            collapsed: false,
+            priority: Priority::None,
+            tags: Vec::new(),
+            carried: false,
+        })
+    }
+
+    fn from_template(db: &Connection, template: &DailyTodo, day_id: i64) -> Result<Self> {
+        let (text, tags) = parse_tags(&template.text);
+        let mut todo = Self::new(db, &text, day_id)?;
+        todo.set_tags(db, &tags)?;
+        db.execute(
+            "UPDATE todos SET daily_todo_id = ?1 WHERE id = ?2",
+            [template.id, todo.id],
+        )?;
+        todo.daily_todo_id = Some(template.id);
+        Ok(todo)
+    }
+
+    /// Copies an unfinished todo into another day, remembering the todo it
+    /// came from and the day it was first planned for.
+    pub fn carry(&self, db: &Connection, day_id: i64, deferred_since: &str) -> Result<Self> {
+        let mut todo = Self::new(db, &self.text, day_id)?;
+        db.execute(
+            "UPDATE todos SET due_date = ?1, carried_from = ?2, deferred_since = ?3, priority = ?4
+            WHERE id = ?5",
+            params![
+                self.due_date,
+                self.id,
+                deferred_since,
+                self.priority,
+                todo.id
+            ],
+        )?;
+        todo.due_date = self.due_date.clone();
+        todo.priority = self.priority;
+        todo.deferred_since = Some(deferred_since.to_string());
+        todo.set_tags(db, &self.tags)?;
+        Ok(todo)
+    }
+
+    fn from_row(row: &Row) -> Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            day_id: row.get(1)?,
+            position: row.get(2)?,
+            text: row.get(3)?,
+            completed: row.get(4)?,
+            due_date: row.get(5)?,
+            daily_todo_id: row.get(6)?,
+            deferred_since: row.get(7)?,
+            parent_id: row.get(8)?,
+            collapsed: row.get(9)?,
+            priority: row.get(10)?,
+            tags: row
+                .get::<_, Option<String>>(11)?
+                .map(|tags| tags.split(' ').map(String::from).collect())
+                .unwrap_or_default(),
+            carried: row.get(12)?,
+        })
+    }
+
+    pub fn get(db: &Connection, id: i64) -> Result<Self> {
+        db.query_row(
+            &format!(
+                "SELECT {} FROM todos WHERE id = ?1 AND deleted_at IS NULL",
+                TODO_COLUMNS
+            ),
+            [id],
+            Self::from_row,
+        )
+    }
+
+    pub fn get_all(db: &Connection, day_id: i64) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare(&format!(
+            "SELECT {} FROM todos WHERE day_id = ?1 AND deleted_at IS NULL
+            ORDER BY position ASC",
+            TODO_COLUMNS
+        ))?;
+        let todos: Vec<Self> = stmt
+            .query_map([day_id], Self::from_row)?
+            .filter_map(Result::ok)
+            .collect();
+        Ok(todos)
+    }
+
+    /// Unfinished todos that are due on or before `date`, oldest due date
+    /// first, leaving out the todos of `exclude_day_id` and todos that were
+    /// carried over, whose copy is the one that counts.
+    pub fn get_due(db: &Connection, date: &str, exclude_day_id: Option<i64>) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare(&format!(
+            "SELECT {} FROM todos
+            WHERE completed = 0 AND due_date <= ?1 AND (?2 IS NULL OR day_id != ?2)
+                AND deleted_at IS NULL
+                AND NOT EXISTS (SELECT 1 FROM todos AS copies WHERE copies.carried_from = todos.id)
+            ORDER BY due_date ASC, id ASC",
+            TODO_COLUMNS
+        ))?;
+        let todos: Vec<Self> = stmt
+            .query_map(params![date, exclude_day_id], Self::from_row)?
+            .filter_map(Result::ok)
+            .collect();
+        Ok(todos)
+    }
+
+    pub fn get_text(&self) -> String {
+        let text = if self.completed {
+            format!("{} {}{}", "[x]", self.priority.marker(), self.text)
+        } else {
+            format!("{} {}{}", "[ ]", self.priority.marker(), self.text)
+        };
+        let text = self
+            .tags
+            .iter()
+            .fold(text, |text, tag| format!("{} #{}", text, tag));
+        match &self.due_date {
+            Some(due_date) => format!("{} (due {})", text, due_date),
+            None => text,
+        }
+    }
+
+    pub fn is_overdue(&self, today: &str) -> bool {
+        !self.completed && matches!(&self.due_date, Some(due_date) if due_date.as_str() < today)
+    }
+
+    /// Number of days between the day the todo was first planned for and
+    /// `date`, for todos that were carried over.
+    pub fn deferred_days(&self, date: &str) -> Option<i64> {
+        let since =
+            NaiveDate::parse_from_str(self.deferred_since.as_deref()?, date::FORMAT).ok()?;
+        let date = NaiveDate::parse_from_str(date, date::FORMAT).ok()?;
+        Some((date - since).num_days())
+    }
+
+    pub fn toggle(&mut self, db: &Connection) -> Result<()> {
+        db.execute(
+            "UPDATE todos SET completed = 1 - completed WHERE id = ?1",
+            [&self.id],
+        )?;
+        self.completed = !self.completed;
+        Ok(())
+    }
+
+    pub fn set_text(&mut self, db: &Connection, text: &str) -> Result<()> {
+        db.execute(
+            "UPDATE todos SET text = ?1 WHERE id = ?2",
+            [text, self.id.to_string().as_str()],
+        )?;
+        self.text = text.to_string();
+        Ok(())
+    }
+
+    pub fn set_due_date(&mut self, db: &Connection, due_date: Option<&str>) -> Result<()> {
+        db.execute(
+            "UPDATE todos SET due_date = ?1 WHERE id = ?2",
+            params![due_date, self.id],
+        )?;
+        self.due_date = due_date.map(String::from);
+        Ok(())
+    }
+
+    pub fn set_priority(&mut self, db: &Connection, priority: Priority) -> Result<()> {
+        db.execute(
+            "UPDATE todos SET priority = ?1 WHERE id = ?2",
+            params![priority, self.id],
+        )?;
+        self.priority = priority;
+        Ok(())
+    }
+
+    pub fn set_parent(&mut self, db: &Connection, parent_id: Option<i64>) -> Result<()> {
+        db.execute(
+            "UPDATE todos SET parent_id = ?1 WHERE id = ?2",
+            params![parent_id, self.id],
+        )?;
+        self.parent_id = parent_id;
+        Ok(())
+    }
+
+    /// Moves the todo to the end of another day's list.
+    pub fn move_to(&mut self, db: &Connection, day_id: i64) -> Result<()> {
+        db.execute(
+            "UPDATE todos SET day_id = ?1,
+            position = (SELECT IFNULL(MAX(position), 0) + 1 FROM todos WHERE day_id = ?1)
+            WHERE id = ?2",
+            [day_id, self.id],
+        )?;
+        self.day_id = day_id;
+        Ok(())
+    }
+
+    pub fn set_completed(&mut self, db: &Connection, completed: bool) -> Result<()> {
+        db.execute(
+            "UPDATE todos SET completed = ?1 WHERE id = ?2",
+            [completed as i64, self.id],
+        )?;
+        self.completed = completed;
+        Ok(())
+    }
+
+    /// Moves `todos` to the trash as one item under the first of them, see
+    /// `TrashItem` to restore or purge it.
+    pub fn delete_all(db: &Connection, todos: &[Self]) -> Result<()> {
+        let root = match todos.first() {
+            Some(root) => root.id,
+            None => return Ok(()),
+        };
+        let placeholders = vec!["?"; todos.len()].join(", ");
+        db.execute(
+            &format!(
+                "UPDATE todos SET deleted_at = datetime('now', 'localtime'), deleted_with = ?
+                WHERE id IN ({})",
+                placeholders
+            ),
+            params_from_iter(std::iter::once(root).chain(todos.iter().map(|todo| todo.id))),
+        )?;
+        Ok(())
+    }
+
+    pub fn update_positions(db: &Connection, todos: &[Self]) -> Result<()> {
+        for (i, todo) in todos.iter().enumerate() {
+            db.execute(
+                "UPDATE todos SET position = ?1 WHERE id = ?2",
+                [&i.to_string().as_str(), &todo.id.to_string().as_str()],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub struct DailyTodo {
+    pub id: i64,
+    #[allow(dead_code)]
+    pub position: i64,
+    pub text: String,
+    pub recurrence: Recurrence,
+}
+
+impl DailyTodo {
+    pub fn new(db: &Connection, text: &str) -> Result<Self> {
+        db.execute("INSERT INTO daily_todos (text) VALUES (?1)", [text])?;
+        let id = db.last_insert_rowid();
+        db.execute(
+            "UPDATE daily_todos SET position = ?1 WHERE id = ?2",
+            [&id, &id],
+        )?;
+        Ok(Self {
+            id,
+            position: id,
+            text: String::from(text),
+            recurrence: Recurrence::Daily,
+        })
+    }
+
+    pub fn get_all(db: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare(
+            "SELECT id, position, text, recurrence FROM daily_todos WHERE deleted_at IS NULL
+            ORDER BY position ASC",
+        )?;
+        let days: Vec<Self> = stmt
+            .query_map([], |r| {
+                let recurrence: String = r.get(3)?;
+                Ok(Self {
+                    id: r.get(0)?,
+                    position: r.get(1)?,
+                    text: r.get(2)?,
+                    recurrence: recurrence.parse().map_err(|e: String| {
+                        rusqlite::Error::FromSqlConversionFailure(
+                            3,
+                            rusqlite::types::Type::Text,
+                            e.into(),
+                        )
+                    })?,
+                })
+            })?
+            .filter_map(Result::ok)
+            .collect();
+        Ok(days)
+    }
+
+    pub fn update_positions(db: &Connection, todos: &[Self]) -> Result<()> {
+        for (i, todo) in todos.iter().enumerate() {
+            db.execute(
+                "UPDATE daily_todos SET position = ?1 WHERE id = ?2",
+                [&i.to_string().as_str(), &todo.id.to_string().as_str()],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn get_text(&self) -> String {
+        match self.recurrence {
+            Recurrence::Daily => self.text.to_string(),
+            _ => format!("{} ({})", self.text, self.recurrence),
+        }
+    }
+
+    pub fn set_recurrence(&mut self, db: &Connection, recurrence: Recurrence) -> Result<()> {
+        db.execute(
+            "UPDATE daily_todos SET recurrence = ?1 WHERE id = ?2",
+            params![recurrence.to_string(), self.id],
+        )?;
+        self.recurrence = recurrence;
+        Ok(())
+    }
+
+    pub fn set_text(&mut self, db: &Connection, text: &str) -> Result<()> {
+        db.execute(
+            "UPDATE daily_todos SET text = ?1 WHERE id = ?2",
+            [text, self.id.to_string().as_str()],
+        )?;
+        self.text = text.to_string();
+        Ok(())
+    }
+
+    pub fn delete(&self, db: &Connection) -> Result<()> {
+        db.execute(
+            "UPDATE daily_todos SET deleted_at = datetime('now', 'localtime') WHERE id = ?1",
+            [&self.id],
+        )?;
+        Ok(())
+    }
+}
+
+pub struct Day {
+    pub id: i64,
+    pub count_todos: i64,
+    pub done_todos: i64,
+    pub notes: String,
+    pub date: String,
+    pub planned: bool,
+    pub rolled_over: bool,
+    pub todos: Vec<Todo>,
+}
+
+impl Day {
+    pub fn new(db: &Connection, date: &str) -> Result<Self> {
+        db.execute(
+            "INSERT INTO days (count_todos, done_todos, notes, date, rolled_over) VALUES (0,0,'',?1,0)",
+            [date],
+        )?;
+        let id = db.last_insert_rowid();
+        let mut day = Self {
+            id,
+            count_todos: 0,
+            done_todos: 0,
+            notes: String::new(),
+            date: String::from(date),
+            planned: false,
+            rolled_over: false,
+            todos: Vec::new(),
+        };
+        day.add_daily_todos(db)?;
+        Ok(day)
+    }
+
+    /// Creates a day ahead of time to hold scheduled todos. The daily todo
+    /// templates are only copied once the day is started.
+    pub fn plan(db: &Connection, date: &str) -> Result<Self> {
+        if let Some(day) = Self::find_by_date(db, date)? {
+            return Ok(day);
+        }
+        db.execute(
+            "INSERT INTO days (count_todos, done_todos, notes, date, planned, rolled_over) VALUES (0,0,'',?1,1,0)",
+            [date],
+        )?;
+        Ok(Self {
+            id: db.last_insert_rowid(),
+            count_todos: 0,
+            done_todos: 0,
+            notes: String::new(),
+            date: String::from(date),
+            planned: true,
+            rolled_over: false,
+            todos: Vec::new(),
+        })
+    }
+
+    /// Creates a day that has already passed, without copying the daily
+    /// todos into it.
+    pub fn past(db: &Connection, date: &str) -> Result<Self> {
+        if let Some(day) = Self::find_by_date(db, date)? {
+            return Ok(day);
+        }
+        db.execute(
+            "INSERT INTO days (count_todos, done_todos, notes, date, rolled_over) VALUES (0,0,'',?1,1)",
+            [date],
+        )?;
+        Ok(Self {
+            id: db.last_insert_rowid(),
+            count_todos: 0,
+            done_todos: 0,
+            notes: String::new(),
+            date: String::from(date),
+            planned: false,
+            rolled_over: true,
+            todos: Vec::new(),
+        })
+    }
+
+    fn add_daily_todos(&mut self, db: &Connection) -> Result<()> {
+        let date = NaiveDate::parse_from_str(&self.date, date::FORMAT)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))?;
+        for daily_todo in DailyTodo::get_all(db)? {
+            if !daily_todo.recurrence.occurs_on(date) {
+                continue;
+            }
+            let todo = Todo::from_template(db, &daily_todo, self.id)?;
+            self.todos.push(todo);
+        }
+        self.update_counts(db)
+    }
+
+    /// Turns a planned day into a regular one by copying the daily todos.
+    pub fn start(&mut self, db: &Connection) -> Result<()> {
+        if self.planned {
+            db.execute("UPDATE days SET planned = 0 WHERE id = ?1", [self.id])?;
+            self.planned = false;
+            self.add_daily_todos(db)?;
+        }
+        Ok(())
+    }
+
+    pub fn get(db: &Connection, day_id: i64) -> Result<Self> {
+        let mut stmt = db.prepare(
+            "SELECT id, count_todos, done_todos, notes, date, planned, rolled_over FROM days WHERE id = ?1 LIMIT 1",
+        )?;
+        let day = stmt.query_row([day_id], |r| {
+            let id = r.get(0)?;
+            let todos = Self::tree_order(Todo::get_all(db, id)?);
+            Ok(Self {
+                id,
+                count_todos: r.get(1)?,
+                done_todos: r.get(2)?,
+                notes: r.get(3)?,
+                date: r.get(4)?,
+                planned: r.get(5)?,
+                rolled_over: r.get(6)?,
+                todos,
+            })
+        })?;
+        Ok(day)
+    }
+
+    pub fn find_by_date(db: &Connection, date: &str) -> Result<Option<Self>> {
+        let id: Option<i64> = db
+            .query_row(
+                "SELECT id FROM days WHERE date = ?1 ORDER BY id DESC LIMIT 1",
+                [date],
+                |r| r.get(0),
+            )
+            .optional()?;
+        id.map(|id| Self::get(db, id)).transpose()
+    }
+
+    /// The most recent day the user worked on, up to and including `today`.
+    /// Days planned ahead only count once their date has come.
+    pub fn latest(db: &Connection, today: &str) -> Result<Option<Self>> {
+        let id: Option<i64> = db
+            .query_row(
+                "SELECT id FROM days WHERE date < ?1 AND planned = 0 OR date = ?1
+                ORDER BY date DESC, id DESC LIMIT 1",
+                [today],
+                |r| r.get(0),
+            )
+            .optional()?;
+        id.map(|id| Self::get(db, id)).transpose()
+    }
+
+    /// The last day before this one that was actually started.
+    pub fn previous(&self, db: &Connection) -> Result<Option<Self>> {
+        let id: Option<i64> = db
+            .query_row(
+                "SELECT id FROM days WHERE date < ?1 AND planned = 0
+                ORDER BY date DESC, id DESC LIMIT 1",
+                [&self.date],
+                |r| r.get(0),
+            )
+            .optional()?;
+        id.map(|id| Self::get(db, id)).transpose()
+    }
+
+    /// Unfinished todos that can be carried over to the next day. Copies of
+    /// daily todos are left out since the next day gets its own, and so are
+    /// todos already carried somewhere else.
+    pub fn unfinished(&self) -> impl Iterator<Item = &Todo> {
+        self.todos
+            .iter()
+            .filter(|todo| !todo.completed && todo.daily_todo_id.is_none() && !todo.carried)
+    }
+
+    /// Copies `todos` from `from` into this day and marks the day as rolled
+    /// over, so the carry-over is only offered once.
+    pub fn carry_over(&mut self, db: &Connection, from: &Day, todos: &[&Todo]) -> Result<()> {
+        // Old todo id to its copy, so that carried sub-tasks keep their parent.
+        let mut copies = Vec::new();
+        for todo in todos {
+            let since = todo.deferred_since.as_deref().unwrap_or(&from.date);
+            let mut copy = todo.carry(db, self.id, since)?;
+            if let Some(&(_, parent)) = copies.iter().find(|(id, _)| Some(*id) == todo.parent_id) {
+                copy.set_parent(db, Some(parent))?;
+            }
+            copies.push((todo.id, copy.id));
+            self.todos.push(copy);
+        }
+        self.set_rolled_over(db)?;
+        self.update_counts(db)
+    }
+
+    /// Moves the unfinished todos of `from` into this day instead of copying
+    /// them, and marks the day as rolled over.
+    pub fn move_over(&mut self, db: &Connection, from: &mut Day) -> Result<()> {
+        let ids: Vec<i64> = from.unfinished().map(|todo| todo.id).collect();
+        let (moved, kept) = std::mem::take(&mut from.todos)
+            .into_iter()
+            .partition(|todo| ids.contains(&todo.id));
+        from.todos = kept;
+        for mut todo in moved {
+            todo.move_to(db, self.id)?;
+            self.todos.push(todo);
+        }
+        from.update_counts(db)?;
+        self.set_rolled_over(db)?;
+        self.update_counts(db)
+    }
+
+    pub fn set_rolled_over(&mut self, db: &Connection) -> Result<()> {
+        db.execute("UPDATE days SET rolled_over = 1 WHERE id = ?1", [self.id])?;
+        self.rolled_over = true;
+        Ok(())
+    }
+
+    pub fn get_or_create(db: &Connection, date: &str) -> Result<Self> {
+        match Self::find_by_date(db, date)? {
+            Some(mut day) => {
+                day.start(db)?;
+                Ok(day)
+            }
+            None => Self::new(db, date),
+        }
+    }
+
+    /// The day of `date`, created if needed: today is started as usual, later
+    /// days are planned and earlier ones are created without the daily todos
+    /// they never had.
+    pub fn for_date(db: &Connection, date: &str) -> Result<Self> {
+        let today = date::today();
+        if date == today {
+            Self::get_or_create(db, date)
+        } else if date > today.as_str() {
+            Self::plan(db, date)
+        } else {
+            Self::past(db, date)
+        }
+    }
+
+    pub fn set_notes(&mut self, db: &Connection) -> Result<()> {
+        db.execute(
+            "UPDATE days SET notes = ?1 WHERE id = ?2",
+            [&self.notes, self.id.to_string().as_str()],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_counts(&mut self, db: &Connection) -> Result<()> {
+        self.count_todos = self.todos.len() as i64;
+        self.done_todos = self.todos.iter().filter(|t| t.completed).count() as i64;
+        db.execute(
+            "UPDATE days SET count_todos = ?1, done_todos = ?2 WHERE id = ?3",
+            [&self.count_todos, &self.done_todos, &self.id],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_todo(&mut self, db: &Connection, item: Todo) -> Result<()> {
+        self.todos.push(item);
+        self.update_counts(db)?;
+        Ok(())
+    }
+
+    /// Adds a todo read from a file and returns its id.
+    pub fn add_new(&mut self, db: &Connection, new: &NewTodo) -> Result<i64> {
+        let mut todo = Todo::new(db, new.text.trim(), self.id)?;
+        todo.set_tags(db, new.tags)?;
+        if new.completed {
+            todo.set_completed(db, true)?;
+        }
+        if new.priority != Priority::None {
+            todo.set_priority(db, new.priority)?;
+        }
+        if new.due_date.is_some() {
+            todo.set_due_date(db, new.due_date)?;
+        }
+        if new.parent.is_some() {
+            todo.set_parent(db, new.parent)?;
+        }
+        let id = todo.id;
+        self.add_todo(db, todo)?;
+        Ok(id)
+    }
+}
+
+/// A todo with everything an import or a todo.txt line can carry.
+pub struct NewTodo<'a> {
+    pub text: &'a str,
+    pub completed: bool,
+    pub priority: Priority,
+    pub due_date: Option<&'a str>,
+    pub tags: &'a [String],
+    pub parent: Option<i64>,
+}
+
+pub struct DayShort {
+    pub id: i64,
+    pub date: String,
+    pub string: String,
+    pub done: usize,
+}
+
+impl DayShort {
+    /// Every day with its completed/total counts. With a tag only the todos
+    /// carrying that tag are counted.
+    pub fn get_all(db: &Connection, tag: Option<&str>) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare(
+            "SELECT days.id, date, COUNT(todos.id), IFNULL(SUM(todos.completed), 0)
+            FROM days LEFT JOIN todos ON todos.day_id = days.id AND todos.deleted_at IS NULL
+            AND (?1 IS NULL OR todos.id IN (
+                SELECT todo_id FROM todo_tags
+                JOIN tags ON tags.id = todo_tags.tag_id WHERE tags.name = ?1
+            ))
+            GROUP BY days.id ORDER BY date, days.id",
+        )?;
+        let days: Vec<Self> = stmt
+            .query_map([tag], |r| {
+                let count: usize = r.get(2)?;
+                let done: usize = r.get(3)?;
+                Ok(Self {
+                    id: r.get(0)?,
+                    date: r.get(1)?,
+                    string: format!("{}/{}", done, count),
+                    done,
+                })
+            })?
+            .filter_map(Result::ok)
+            .collect();
+        Ok(days)
+    }
+}
+
+pub fn init_connection(path: &Path) -> Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    migrations::migrate(&mut conn)?;
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open() -> Connection {
+        init_connection(Path::new(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn past_dates_are_not_planned() {
+        let db = open();
+        let yesterday = date::parse("yesterday").unwrap();
+        let tomorrow = date::parse("tomorrow").unwrap();
+        let past = Day::for_date(&db, &yesterday).unwrap();
+        assert!(!past.planned);
+        assert!(Day::for_date(&db, &tomorrow).unwrap().planned);
+        let latest = Day::latest(&db, &date::today()).unwrap().unwrap();
+        assert_eq!(latest.id, past.id);
+    }
+
+    #[test]
+    fn carried_originals_are_neither_due_nor_unfinished() {
+        let db = open();
+        let yesterday = date::parse("yesterday").unwrap();
+        let mut from = Day::for_date(&db, &yesterday).unwrap();
+        let mut todo = Todo::new(&db, "file taxes", from.id).unwrap();
+        todo.set_due_date(&db, Some(&yesterday)).unwrap();
+        from.add_todo(&db, todo).unwrap();
+        let mut to = Day::for_date(&db, &date::today()).unwrap();
+        let unfinished: Vec<&Todo> = from.unfinished().collect();
+        to.carry_over(&db, &from, &unfinished).unwrap();
+
+        let from = Day::get(&db, from.id).unwrap();
+        assert_eq!(from.unfinished().count(), 0);
+        let due = Todo::get_due(&db, &date::today(), Some(to.id)).unwrap();
+        assert!(due.is_empty());
+        let due = Todo::get_due(&db, &date::today(), None).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].day_id, to.id);
+    }
+
+    #[test]
+    fn carried_todos_keep_their_priority() {
+        let db = open();
+        let from = Day::for_date(&db, &date::parse("yesterday").unwrap()).unwrap();
+        let mut todo = Todo::new(&db, "renew passport", from.id).unwrap();
+        todo.set_priority(&db, Priority::High).unwrap();
+        let to = Day::for_date(&db, &date::today()).unwrap();
+        let copy = todo.carry(&db, to.id, &from.date).unwrap();
+        assert!(copy.priority == Priority::High);
+        assert!(Todo::get(&db, copy.id).unwrap().priority == Priority::High);
+    }
+
+    #[test]
+    fn move_over_leaves_done_todos_behind() {
+        let db = open();
+        let mut from = Day::for_date(&db, &date::parse("yesterday").unwrap()).unwrap();
+        for (text, done) in [("open", false), ("done", true)] {
+            let mut todo = Todo::new(&db, text, from.id).unwrap();
+            todo.set_completed(&db, done).unwrap();
+            from.add_todo(&db, todo).unwrap();
+        }
+        let mut to = Day::for_date(&db, &date::today()).unwrap();
+        to.move_over(&db, &mut from).unwrap();
+        let texts = |day: &Day| -> Vec<String> {
+            let day = Day::get(&db, day.id).unwrap();
+            assert_eq!(day.count_todos, day.todos.len() as i64);
+            day.todos.into_iter().map(|todo| todo.text).collect()
+        };
+        assert_eq!(texts(&from), ["done"]);
+        assert_eq!(texts(&to), ["open"]);
+        assert!(Day::get(&db, to.id).unwrap().rolled_over);
+    }
+}
@@ -0,0 +1,173 @@
+use super::{parse_tags, Day, Todo};
+use rusqlite::{params, Connection, Result};
+
+/// A named list of todos that is not tied to a day, like a backlog.
+pub struct Project {
+    pub id: i64,
+    pub name: String,
+    pub todos: Vec<ProjectTodo>,
+}
+
+impl Project {
+    pub fn new(db: &Connection, name: &str) -> Result<Self> {
+        db.execute(
+            "INSERT INTO projects (name, position)
+            VALUES (?1, (SELECT IFNULL(MAX(position), 0) + 1 FROM projects))",
+            [name],
+        )?;
+        Ok(Self {
+            id: db.last_insert_rowid(),
+            name: String::from(name),
+            todos: Vec::new(),
+        })
+    }
+
+    pub fn get_all(db: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare("SELECT id, name FROM projects ORDER BY position ASC")?;
+        let projects: Vec<Self> = stmt
+            .query_map([], |r| {
+                let id = r.get(0)?;
+                Ok(Self {
+                    id,
+                    name: r.get(1)?,
+                    todos: ProjectTodo::get_all(db, id)?,
+                })
+            })?
+            .filter_map(Result::ok)
+            .collect();
+        Ok(projects)
+    }
+
+    pub fn find_by_name(db: &Connection, name: &str) -> Result<Option<Self>> {
+        Ok(Self::get_all(db)?
+            .into_iter()
+            .find(|project| project.name.eq_ignore_ascii_case(name)))
+    }
+
+    pub fn update_positions(db: &Connection, projects: &[Self]) -> Result<()> {
+        for (i, project) in projects.iter().enumerate() {
+            db.execute(
+                "UPDATE projects SET position = ?1 WHERE id = ?2",
+                params![i as i64, project.id],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn rename(&mut self, db: &Connection, name: &str) -> Result<()> {
+        db.execute(
+            "UPDATE projects SET name = ?1 WHERE id = ?2",
+            params![name, self.id],
+        )?;
+        self.name = String::from(name);
+        Ok(())
+    }
+
+    /// Deletes the project together with its todos.
+    pub fn delete(&self, db: &Connection) -> Result<()> {
+        db.execute("DELETE FROM project_todos WHERE project_id = ?1", [self.id])?;
+        db.execute("DELETE FROM projects WHERE id = ?1", [self.id])?;
+        Ok(())
+    }
+
+    pub fn add_todo(&mut self, db: &Connection, text: &str) -> Result<()> {
+        let todo = ProjectTodo::new(db, self.id, text)?;
+        self.todos.push(todo);
+        Ok(())
+    }
+
+    /// Moves a todo out of the project into `day`, `#tags` in its text
+    /// become tags of the new todo.
+    pub fn pull(&mut self, db: &Connection, index: usize, day: &mut Day) -> Result<()> {
+        let item = self.todos.remove(index);
+        let (text, tags) = parse_tags(&item.text);
+        let mut todo = Todo::new(db, &text, day.id)?;
+        todo.set_tags(db, &tags)?;
+        item.delete(db)?;
+        day.add_todo(db, todo)
+    }
+}
+
+pub struct ProjectTodo {
+    pub id: i64,
+    #[allow(dead_code)]
+    pub position: i64,
+    pub text: String,
+    pub completed: bool,
+}
+
+impl ProjectTodo {
+    fn new(db: &Connection, project_id: i64, text: &str) -> Result<Self> {
+        db.execute(
+            "INSERT INTO project_todos (project_id, text) VALUES (?1, ?2)",
+            params![project_id, text],
+        )?;
+        let id = db.last_insert_rowid();
+        db.execute(
+            "UPDATE project_todos SET position = ?1 WHERE id = ?2",
+            [id, id],
+        )?;
+        Ok(Self {
+            id,
+            position: id,
+            text: String::from(text),
+            completed: false,
+        })
+    }
+
+    fn get_all(db: &Connection, project_id: i64) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare(
+            "SELECT id, position, text, completed FROM project_todos
+            WHERE project_id = ?1 ORDER BY position ASC",
+        )?;
+        let todos: Vec<Self> = stmt
+            .query_map([project_id], |r| {
+                Ok(Self {
+                    id: r.get(0)?,
+                    position: r.get(1)?,
+                    text: r.get(2)?,
+                    completed: r.get(3)?,
+                })
+            })?
+            .filter_map(Result::ok)
+            .collect();
+        Ok(todos)
+    }
+
+    pub fn update_positions(db: &Connection, todos: &[Self]) -> Result<()> {
+        for (i, todo) in todos.iter().enumerate() {
+            db.execute(
+                "UPDATE project_todos SET position = ?1 WHERE id = ?2",
+                params![i as i64, todo.id],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn get_text(&self) -> String {
+        format!("[{}] {}", if self.completed { "x" } else { " " }, self.text)
+    }
+
+    pub fn toggle(&mut self, db: &Connection) -> Result<()> {
+        db.execute(
+            "UPDATE project_todos SET completed = ?1 WHERE id = ?2",
+            params![!self.completed, self.id],
+        )?;
+        self.completed = !self.completed;
+        Ok(())
+    }
+
+    pub fn set_text(&mut self, db: &Connection, text: &str) -> Result<()> {
+        db.execute(
+            "UPDATE project_todos SET text = ?1 WHERE id = ?2",
+            params![text, self.id],
+        )?;
+        self.text = String::from(text);
+        Ok(())
+    }
+
+    pub fn delete(&self, db: &Connection) -> Result<()> {
+        db.execute("DELETE FROM project_todos WHERE id = ?1", [self.id])?;
+        Ok(())
+    }
+}
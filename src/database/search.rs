@@ -0,0 +1,250 @@
+use rusqlite::{params, Connection, Result, Row};
+
+pub enum EntryKind {
+    Todo { completed: bool },
+    Note,
+}
+
+/// A todo or a line of notes together with the day it belongs to, what
+/// the search screen matches against.
+pub struct Entry {
+    pub day_id: i64,
+    pub date: String,
+    pub kind: EntryKind,
+    pub text: String,
+}
+
+impl Entry {
+    /// Every todo and non-empty notes line of every day, newest day first.
+    pub fn get_all(db: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare(
+            "SELECT days.id, days.date, todos.text, todos.completed,
+                (SELECT group_concat('#' || name, ' ') FROM tags
+                WHERE id IN (SELECT tag_id FROM todo_tags WHERE todo_id = todos.id))
+            FROM todos JOIN days ON days.id = todos.day_id
+            WHERE todos.deleted_at IS NULL
+            ORDER BY days.date DESC, todos.position ASC",
+        )?;
+        let mut entries: Vec<Self> = stmt
+            .query_map([], |r| {
+                let text: String = r.get(2)?;
+                let tags: Option<String> = r.get(4)?;
+                Ok(Self {
+                    day_id: r.get(0)?,
+                    date: r.get(1)?,
+                    kind: EntryKind::Todo {
+                        completed: r.get(3)?,
+                    },
+                    text: match tags {
+                        Some(tags) => format!("{} {}", text, tags),
+                        None => text,
+                    },
+                })
+            })?
+            .filter_map(Result::ok)
+            .collect();
+        let mut stmt = db.prepare("SELECT id, date, notes FROM days WHERE notes != ''")?;
+        let days = stmt.query_map([], |r| {
+            Ok((
+                r.get::<_, i64>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+            ))
+        })?;
+        for (day_id, date, notes) in days.filter_map(Result::ok) {
+            entries.extend(
+                notes
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(|line| Self {
+                        day_id,
+                        date: date.clone(),
+                        kind: EntryKind::Note,
+                        text: String::from(line),
+                    }),
+            );
+        }
+        // Stable, so todos stay in order and come before the notes of a day.
+        entries.sort_by(|a, b| b.date.cmp(&a.date));
+        Ok(entries)
+    }
+}
+
+// Marks matches in FTS snippets, split off again by `Hit::from_row`.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+/// A search result: where it was found and a snippet split into plain and
+/// matching parts, `true` marking a match.
+pub struct Hit {
+    pub day_id: i64,
+    pub date: String,
+    pub kind: EntryKind,
+    pub snippet: Vec<(String, bool)>,
+}
+
+impl Hit {
+    /// Wraps a fuzzy match of `entry`, `positions` being the matched chars.
+    pub fn from_entry(entry: &Entry, positions: &[usize]) -> Self {
+        let mut snippet: Vec<(String, bool)> = Vec::new();
+        for (index, c) in entry.text.chars().enumerate() {
+            let matched = positions.contains(&index);
+            match snippet.last_mut() {
+                Some((part, m)) if *m == matched => part.push(c),
+                _ => snippet.push((c.to_string(), matched)),
+            }
+        }
+        Self {
+            day_id: entry.day_id,
+            date: entry.date.clone(),
+            kind: match entry.kind {
+                EntryKind::Todo { completed } => EntryKind::Todo { completed },
+                EntryKind::Note => EntryKind::Note,
+            },
+            snippet,
+        }
+    }
+
+    fn from_row(r: &Row) -> Result<Self> {
+        let completed: Option<bool> = r.get(2)?;
+        let text: String = r.get(3)?;
+        let mut snippet = Vec::new();
+        for (i, part) in text.split([MATCH_START, MATCH_END]).enumerate() {
+            if !part.is_empty() {
+                // Parts alternate between outside and inside of the markers.
+                snippet.push((part.replace('\n', " "), i % 2 == 1));
+            }
+        }
+        Ok(Self {
+            day_id: r.get(0)?,
+            date: r.get(1)?,
+            kind: match completed {
+                Some(completed) => EntryKind::Todo { completed },
+                None => EntryKind::Note,
+            },
+            snippet,
+        })
+    }
+
+    /// Full-text search over todo texts and day notes, best match first.
+    /// `query` is turned into an FTS5 query by `fts_query`.
+    pub fn search(db: &Connection, query: &str, limit: usize) -> Result<Vec<Self>> {
+        let query = fts_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut stmt = db.prepare(
+            "SELECT days.id, days.date, todos.completed,
+                snippet(todos_fts, 0, ?2, ?3, '…', 16), bm25(todos_fts) AS rank
+            FROM todos_fts
+            JOIN todos ON todos.id = todos_fts.rowid
+            JOIN days ON days.id = todos.day_id
+            WHERE todos_fts MATCH ?1 AND todos.deleted_at IS NULL
+            UNION ALL
+            SELECT days.id, days.date, NULL,
+                snippet(notes_fts, 0, ?2, ?3, '…', 16), bm25(notes_fts) AS rank
+            FROM notes_fts
+            JOIN days ON days.id = notes_fts.rowid
+            WHERE notes_fts MATCH ?1
+            ORDER BY rank ASC, 2 DESC
+            LIMIT ?4",
+        )?;
+        let hits = stmt
+            .query_map(
+                params![
+                    query,
+                    MATCH_START.to_string(),
+                    MATCH_END.to_string(),
+                    limit as i64
+                ],
+                Self::from_row,
+            )?
+            .collect::<Result<Vec<Self>>>()?;
+        Ok(hits)
+    }
+}
+
+/// Turns user input into an FTS5 query without syntax errors: words and
+/// `"quoted phrases"` must all match, a trailing `*` makes a word or phrase
+/// a prefix query. An unterminated quote runs to the end of the input.
+pub fn fts_query(input: &str) -> String {
+    let mut terms = Vec::new();
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        let (term, tail) = match rest.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((phrase, tail)) => (phrase, tail),
+                None => (quoted, ""),
+            },
+            None => rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len())),
+        };
+        let (term, prefix) = match tail.strip_prefix('*') {
+            Some(_) => (term, true),
+            None => match term.strip_suffix('*') {
+                Some(term) => (term, true),
+                None => (term, false),
+            },
+        };
+        let tail = tail.strip_prefix('*').unwrap_or(tail);
+        if !term.trim().is_empty() {
+            let phrase = format!("\"{}\"", term.replace('"', "\"\""));
+            terms.push(if prefix { phrase + "*" } else { phrase });
+        }
+        rest = tail.trim_start();
+    }
+    terms.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{init_connection, Day, Todo};
+    use std::path::Path;
+
+    #[test]
+    fn quotes_every_term() {
+        let cases = [
+            ("milk", r#""milk""#),
+            ("  buy   milk ", r#""buy" "milk""#),
+            (r#""buy milk" eggs"#, r#""buy milk" "eggs""#),
+            ("mil*", r#""mil"*"#),
+            (r#""buy mi"*"#, r#""buy mi"*"#),
+            (r#"say "hi"#, r#""say" "hi""#),
+            (r#"it"s"#, r#""it""s""#),
+            ("a*b", r#""a*b""#),
+            ("NOT done OR", r#""NOT" "done" "OR""#),
+            ("col:val (x)", r#""col:val" "(x)""#),
+            ("*", ""),
+            (r#""""#, ""),
+            ("   ", ""),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(fts_query(input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn searches_without_syntax_errors() {
+        let db = init_connection(Path::new(":memory:")).unwrap();
+        let mut day = Day::plan(&db, "2022-09-01").unwrap();
+        for text in [r#"say "hi" to bob"#, "rated 5* by NOT critics", "milkshake"] {
+            let todo = Todo::new(&db, text, day.id).unwrap();
+            day.add_todo(&db, todo).unwrap();
+        }
+        let cases = [
+            (r#"say "hi"#, 1),
+            (r#""hi" bob"#, 1),
+            ("5*", 1),
+            ("NOT", 1),
+            ("milk", 0),
+            ("milk*", 1),
+            ("(bob", 1),
+            (r#"""#, 0),
+        ];
+        for (query, found) in cases {
+            let hits = Hit::search(&db, query, 10).unwrap();
+            assert_eq!(hits.len(), found, "{:?}", query);
+        }
+    }
+}
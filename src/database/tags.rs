@@ -0,0 +1,121 @@
+use super::Todo;
+use rusqlite::{params, Connection, Result};
+
+pub struct Tag {
+    #[allow(dead_code)]
+    pub id: i64,
+    pub name: String,
+}
+
+impl Tag {
+    /// Every tag that is attached to at least one todo, by name.
+    pub fn get_all(db: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare(
+            "SELECT id, name FROM tags
+            WHERE id IN (
+                SELECT tag_id FROM todo_tags
+                JOIN todos ON todos.id = todo_tags.todo_id WHERE todos.deleted_at IS NULL
+            )
+            ORDER BY name ASC",
+        )?;
+        let tags: Vec<Self> = stmt
+            .query_map([], |r| {
+                Ok(Self {
+                    id: r.get(0)?,
+                    name: r.get(1)?,
+                })
+            })?
+            .filter_map(Result::ok)
+            .collect();
+        Ok(tags)
+    }
+}
+
+// Tags start with a letter, so that references like `#42` stay in the text.
+fn is_tag(word: &str) -> bool {
+    match word.strip_prefix('#') {
+        Some(name) => {
+            name.starts_with(char::is_alphabetic)
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '/')
+        }
+        None => false,
+    }
+}
+
+/// Splits `#tag` words off a todo's text. Tags are lowercased and returned
+/// without the `#`, in the order they first appear.
+pub fn parse_tags(text: &str) -> (String, Vec<String>) {
+    let mut words = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        if is_tag(word) {
+            let tag = word[1..].to_lowercase();
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        } else {
+            words.push(word);
+        }
+    }
+    (words.join(" "), tags)
+}
+
+impl Todo {
+    pub fn set_tags(&mut self, db: &Connection, tags: &[String]) -> Result<()> {
+        db.execute("DELETE FROM todo_tags WHERE todo_id = ?1", [self.id])?;
+        for tag in tags {
+            db.execute("INSERT OR IGNORE INTO tags (name) VALUES (?1)", [tag])?;
+            db.execute(
+                "INSERT OR IGNORE INTO todo_tags (todo_id, tag_id)
+                SELECT ?1, id FROM tags WHERE name = ?2",
+                params![self.id, tag],
+            )?;
+        }
+        self.tags = tags.to_vec();
+        Ok(())
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// The text with its tags written back as `#tag`, for editing.
+    pub fn text_with_tags(&self) -> String {
+        self.tags
+            .iter()
+            .fold(self.text.clone(), |text, tag| format!("{} #{}", text, tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_tags_off_the_text() {
+        let cases: &[(&str, &str, &[&str])] = &[
+            ("buy milk #Errands", "buy milk", &["errands"]),
+            (
+                "#work #home call #work back",
+                "call back",
+                &["work", "home"],
+            ),
+            (
+                "plan #q4-goals #team/ops",
+                "plan",
+                &["q4-goals", "team/ops"],
+            ),
+            ("#épicerie list", "list", &["épicerie"]),
+            ("Review PR #42 for alice", "Review PR #42 for alice", &[]),
+            ("fix #1a and # alone", "fix #1a and # alone", &[]),
+            ("C# and #tag!", "C# and #tag!", &[]),
+        ];
+        for &(input, text, tags) in cases {
+            let (parsed, parsed_tags) = parse_tags(input);
+            assert_eq!(parsed, text, "{:?}", input);
+            assert_eq!(parsed_tags, tags, "{:?}", input);
+        }
+    }
+}
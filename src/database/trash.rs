@@ -0,0 +1,215 @@
+use rusqlite::{params, Connection, Result};
+
+/// Where a deleted item came from.
+pub enum TrashKind {
+    /// A todo of the day with this date, `sub_tasks` were deleted with it.
+    Todo {
+        date: String,
+        sub_tasks: usize,
+    },
+    DailyTodo,
+}
+
+/// A deleted todo or daily todo. Sub-tasks deleted together with their
+/// parent are restored and purged along with it and are not listed.
+pub struct TrashItem {
+    pub id: i64,
+    pub kind: TrashKind,
+    pub text: String,
+    pub deleted_at: String,
+}
+
+impl TrashItem {
+    /// Everything in the trash, most recently deleted first.
+    pub fn get_all(db: &Connection) -> Result<Vec<Self>> {
+        let mut stmt = db.prepare(
+            "SELECT todos.id, days.date, todos.text, todos.deleted_at,
+                (SELECT COUNT(*) - 1 FROM todos AS sub_tasks WHERE sub_tasks.deleted_with = todos.id)
+            FROM todos JOIN days ON days.id = todos.day_id
+            WHERE todos.deleted_at IS NOT NULL AND todos.deleted_with = todos.id
+            UNION ALL
+            SELECT id, NULL, text, deleted_at, 0 FROM daily_todos WHERE deleted_at IS NOT NULL
+            ORDER BY 4 DESC, 1 DESC",
+        )?;
+        let items: Vec<Self> = stmt
+            .query_map([], |r| {
+                let date: Option<String> = r.get(1)?;
+                Ok(Self {
+                    id: r.get(0)?,
+                    kind: match date {
+                        Some(date) => TrashKind::Todo {
+                            date,
+                            sub_tasks: r.get(4)?,
+                        },
+                        None => TrashKind::DailyTodo,
+                    },
+                    text: r.get(2)?,
+                    deleted_at: r.get(3)?,
+                })
+            })?
+            .filter_map(Result::ok)
+            .collect();
+        Ok(items)
+    }
+
+    /// Puts the item back where it was. A todo whose parent is gone becomes
+    /// a top-level one.
+    pub fn restore(&self, db: &Connection) -> Result<()> {
+        let tx = db.unchecked_transaction()?;
+        match self.kind {
+            TrashKind::Todo { .. } => {
+                tx.execute(
+                    "UPDATE todos SET deleted_at = NULL, deleted_with = NULL
+                    WHERE deleted_with = ?1",
+                    [self.id],
+                )?;
+                tx.execute(
+                    "UPDATE days SET
+                        count_todos = (SELECT COUNT(*) FROM todos
+                            WHERE day_id = days.id AND deleted_at IS NULL),
+                        done_todos = (SELECT COUNT(*) FROM todos
+                            WHERE day_id = days.id AND deleted_at IS NULL AND completed = 1)
+                    WHERE id = (SELECT day_id FROM todos WHERE id = ?1)",
+                    [self.id],
+                )?;
+            }
+            TrashKind::DailyTodo => {
+                tx.execute(
+                    "UPDATE daily_todos SET deleted_at = NULL WHERE id = ?1",
+                    [self.id],
+                )?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// Deletes the item for good.
+    pub fn purge(&self, db: &Connection) -> Result<()> {
+        let tx = db.unchecked_transaction()?;
+        match self.kind {
+            TrashKind::Todo { .. } => {
+                tx.execute(
+                    "DELETE FROM todo_tags
+                    WHERE todo_id IN (SELECT id FROM todos WHERE deleted_with = ?1)",
+                    [self.id],
+                )?;
+                tx.execute("DELETE FROM todos WHERE deleted_with = ?1", [self.id])?;
+            }
+            TrashKind::DailyTodo => {
+                tx.execute("DELETE FROM daily_todos WHERE id = ?1", [self.id])?;
+            }
+        }
+        tx.commit()
+    }
+}
+
+/// Purges everything deleted more than `days` days ago. Returns the number
+/// of purged rows.
+pub fn purge_expired(db: &Connection, days: u32) -> Result<usize> {
+    let tx = db.unchecked_transaction()?;
+    let cutoff = format!("-{} days", days);
+    let expired = "deleted_at < datetime('now', 'localtime', ?1)";
+    tx.execute(
+        &format!(
+            "DELETE FROM todo_tags WHERE todo_id IN (SELECT id FROM todos WHERE {})",
+            expired
+        ),
+        params![cutoff],
+    )?;
+    let mut purged = tx.execute(
+        &format!("DELETE FROM todos WHERE {}", expired),
+        params![cutoff],
+    )?;
+    purged += tx.execute(
+        &format!("DELETE FROM daily_todos WHERE {}", expired),
+        params![cutoff],
+    )?;
+    tx.commit()?;
+    Ok(purged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{init_connection, Day, Todo};
+    use std::path::Path;
+
+    fn texts(db: &Connection, day: &Day) -> Vec<String> {
+        let day = Day::get(db, day.id).unwrap();
+        day.todos.into_iter().map(|todo| todo.text).collect()
+    }
+
+    fn trash(db: &Connection) -> Vec<(String, usize)> {
+        TrashItem::get_all(db)
+            .unwrap()
+            .into_iter()
+            .map(|item| match item.kind {
+                TrashKind::Todo { sub_tasks, .. } => (item.text, sub_tasks),
+                TrashKind::DailyTodo => (item.text, 0),
+            })
+            .collect()
+    }
+
+    /// A day with `parent`, its sub-tasks `child` and `other`, and `next`.
+    fn day(db: &Connection) -> Day {
+        let mut day = Day::plan(db, "2022-09-01").unwrap();
+        let mut parent = None;
+        for (text, child) in [
+            ("parent", false),
+            ("child", true),
+            ("other", true),
+            ("next", false),
+        ] {
+            let mut todo = Todo::new(db, text, day.id).unwrap();
+            if child {
+                todo.set_parent(db, parent).unwrap();
+            } else {
+                parent = Some(todo.id);
+            }
+            day.add_todo(db, todo).unwrap();
+        }
+        day
+    }
+
+    #[test]
+    fn restore_a_subtree_with_its_sub_tasks() {
+        let db = init_connection(Path::new(":memory:")).unwrap();
+        let mut day = day(&db);
+        day.remove_subtree(&db, 0).unwrap();
+        assert_eq!(texts(&db, &day), ["next"]);
+        assert_eq!(trash(&db), [(String::from("parent"), 2)]);
+
+        TrashItem::get_all(&db).unwrap()[0].restore(&db).unwrap();
+        assert_eq!(texts(&db, &day), ["parent", "child", "other", "next"]);
+        assert!(trash(&db).is_empty());
+        assert_eq!(Day::get(&db, day.id).unwrap().count_todos, 4);
+    }
+
+    #[test]
+    fn keep_a_sub_task_deleted_on_its_own_apart() {
+        let db = init_connection(Path::new(":memory:")).unwrap();
+        let mut day = day(&db);
+        // Both deletions happen within the same second.
+        day.remove_subtree(&db, 1).unwrap();
+        day.remove_subtree(&db, 0).unwrap();
+        let mut items = trash(&db);
+        items.sort();
+        assert_eq!(
+            items,
+            [(String::from("child"), 0), (String::from("parent"), 1)]
+        );
+
+        let parent = TrashItem::get_all(&db)
+            .unwrap()
+            .into_iter()
+            .find(|item| item.text == "parent")
+            .unwrap();
+        parent.restore(&db).unwrap();
+        assert_eq!(texts(&db, &day), ["parent", "other", "next"]);
+        assert_eq!(trash(&db), [(String::from("child"), 0)]);
+
+        TrashItem::get_all(&db).unwrap()[0].purge(&db).unwrap();
+        assert!(trash(&db).is_empty());
+        assert_eq!(texts(&db, &day), ["parent", "other", "next"]);
+    }
+}
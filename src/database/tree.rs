@@ -0,0 +1,320 @@
+use super::{Day, Todo};
+use rusqlite::{params, Connection, Result};
+use std::cmp::Reverse;
+
+/// Sub-tasks are kept right after their parent, so `Day::todos` is the tree
+/// in pre-order and a todo's subtree is always a contiguous range.
+impl Day {
+    /// Reorders todos loaded by position so that children follow their
+    /// parents. Todos whose parent is missing become top-level ones.
+    pub(super) fn tree_order(todos: Vec<Todo>) -> Vec<Todo> {
+        fn visit(parent: Option<i64>, rest: &mut Vec<Todo>, sorted: &mut Vec<Todo>) {
+            while let Some(index) = rest.iter().position(|t| t.parent_id == parent) {
+                let todo = rest.remove(index);
+                let id = todo.id;
+                sorted.push(todo);
+                visit(Some(id), rest, sorted);
+            }
+        }
+        let ids: Vec<i64> = todos.iter().map(|t| t.id).collect();
+        let mut rest: Vec<Todo> = todos
+            .into_iter()
+            .map(|mut todo| {
+                if matches!(todo.parent_id, Some(id) if !ids.contains(&id)) {
+                    todo.parent_id = None;
+                }
+                todo
+            })
+            .collect();
+        let mut sorted = Vec::with_capacity(rest.len());
+        visit(None, &mut rest, &mut sorted);
+        // Anything left is part of a parent cycle, keep it at the top level.
+        for mut todo in rest {
+            todo.parent_id = None;
+            sorted.push(todo);
+        }
+        sorted
+    }
+
+    fn index_of(&self, id: i64) -> Option<usize> {
+        self.todos.iter().position(|t| t.id == id)
+    }
+
+    pub fn depth(&self, index: usize) -> usize {
+        let mut depth = 0;
+        let mut parent = self.todos[index].parent_id;
+        while let Some(i) = parent.and_then(|id| self.index_of(id)) {
+            depth += 1;
+            parent = self.todos[i].parent_id;
+        }
+        depth
+    }
+
+    /// End (exclusive) of the subtree starting at `index`.
+    pub fn subtree_end(&self, index: usize) -> usize {
+        let depth = self.depth(index);
+        let mut end = index + 1;
+        while end < self.todos.len() && self.depth(end) > depth {
+            end += 1;
+        }
+        end
+    }
+
+    pub fn has_children(&self, index: usize) -> bool {
+        self.subtree_end(index) > index + 1
+    }
+
+    /// Completed and total sub-tasks of a todo, nested ones included.
+    pub fn progress(&self, index: usize) -> (usize, usize) {
+        let children = &self.todos[index + 1..self.subtree_end(index)];
+        (
+            children.iter().filter(|t| t.completed).count(),
+            children.len(),
+        )
+    }
+
+    /// Whether the todo, one of its sub-tasks or one of its parents has the
+    /// tag, so that filtering keeps whole branches readable.
+    pub fn matches_tag(&self, index: usize, tag: &str) -> bool {
+        self.todos[index..self.subtree_end(index)]
+            .iter()
+            .any(|t| t.has_tag(tag))
+            || self.ancestors(index).any(|i| self.todos[i].has_tag(tag))
+    }
+
+    fn ancestors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut parent = self.todos[index].parent_id;
+        std::iter::from_fn(move || {
+            let index = parent.and_then(|id| self.index_of(id))?;
+            parent = self.todos[index].parent_id;
+            Some(index)
+        })
+    }
+
+    /// Indices of the todos that are not hidden by a collapsed parent or the
+    /// tag filter, in the order they are shown. Sorting by priority keeps
+    /// sub-tasks under their parent and ties in manual order; `position` is
+    /// left untouched.
+    pub fn display_order(&self, by_priority: bool, tag: Option<&str>) -> Vec<usize> {
+        let mut siblings: Vec<usize> = (0..self.todos.len())
+            .filter(|&i| self.todos[i].parent_id.is_none())
+            .collect();
+        let mut order = Vec::with_capacity(self.todos.len());
+        let mut stack = Vec::new();
+        loop {
+            siblings.retain(|&i| tag.is_none_or(|tag| self.matches_tag(i, tag)));
+            if by_priority {
+                siblings.sort_by_key(|&i| Reverse(self.todos[i].priority));
+            }
+            stack.extend(siblings.into_iter().rev());
+            let index = match stack.pop() {
+                Some(index) => index,
+                None => return order,
+            };
+            order.push(index);
+            let todo = &self.todos[index];
+            siblings = if todo.collapsed {
+                Vec::new()
+            } else {
+                (index + 1..self.subtree_end(index))
+                    .filter(|&i| self.todos[i].parent_id == Some(todo.id))
+                    .collect()
+            };
+        }
+    }
+
+    fn previous_sibling(&self, index: usize) -> Option<usize> {
+        let parent = self.todos[index].parent_id;
+        for i in (0..index).rev() {
+            if self.todos[i].parent_id == parent {
+                return Some(i);
+            }
+            if Some(self.todos[i].id) == parent {
+                break;
+            }
+        }
+        None
+    }
+
+    fn next_sibling(&self, index: usize) -> Option<usize> {
+        let end = self.subtree_end(index);
+        (end < self.todos.len() && self.todos[end].parent_id == self.todos[index].parent_id)
+            .then_some(end)
+    }
+
+    /// Swaps the subtree at `index` with the one of its previous or next
+    /// sibling. Returns the new index of the moved todo.
+    pub fn move_subtree(&mut self, db: &Connection, index: usize, up: bool) -> Result<usize> {
+        let (first, second) = match (up, self.previous_sibling(index), self.next_sibling(index)) {
+            (true, Some(previous), _) => (previous, index),
+            (false, _, Some(next)) => (index, next),
+            _ => return Ok(index),
+        };
+        let end = self.subtree_end(second);
+        self.todos[first..end].rotate_left(second - first);
+        Todo::update_positions(db, &self.todos)?;
+        Ok(if up { first } else { first + end - second })
+    }
+
+    /// Makes the todo the last child of its previous sibling.
+    pub fn indent(&mut self, db: &Connection, index: usize) -> Result<()> {
+        if let Some(previous) = self.previous_sibling(index) {
+            let parent = self.todos[previous].id;
+            self.todos[index].set_parent(db, Some(parent))?;
+            if self.todos[previous].collapsed {
+                self.toggle_collapsed(db, previous)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the todo up one level, right after its former parent's subtree.
+    /// Returns the new index of the todo.
+    pub fn outdent(&mut self, db: &Connection, index: usize) -> Result<usize> {
+        let parent = match self.todos[index].parent_id.and_then(|id| self.index_of(id)) {
+            Some(parent) => parent,
+            None => return Ok(index),
+        };
+        let end = self.subtree_end(index);
+        let parent_end = self.subtree_end(parent);
+        let grandparent = self.todos[parent].parent_id;
+        self.todos[index].set_parent(db, grandparent)?;
+        self.todos[index..parent_end].rotate_left(end - index);
+        Todo::update_positions(db, &self.todos)?;
+        Ok(parent_end - (end - index))
+    }
+
+    pub fn toggle_collapsed(&mut self, db: &Connection, index: usize) -> Result<()> {
+        let todo = &mut self.todos[index];
+        db.execute(
+            "UPDATE todos SET collapsed = ?1 WHERE id = ?2",
+            params![!todo.collapsed, todo.id],
+        )?;
+        todo.collapsed = !todo.collapsed;
+        Ok(())
+    }
+
+    /// Deletes a todo together with its sub-tasks.
+    pub fn remove_subtree(&mut self, db: &Connection, index: usize) -> Result<()> {
+        let end = self.subtree_end(index);
+        Todo::delete_all(db, &self.todos[index..end])?;
+        self.todos.drain(index..end);
+        self.update_counts(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::init_connection;
+    use std::path::Path;
+
+    /// A day holding `outline`, two spaces of indentation per level.
+    fn day(db: &Connection, outline: &[&str]) -> Day {
+        let mut day = Day::plan(db, "2022-09-01").unwrap();
+        let mut parents: Vec<i64> = Vec::new();
+        for line in outline {
+            let depth = (line.len() - line.trim_start().len()) / 2;
+            parents.truncate(depth);
+            let mut todo = Todo::new(db, line.trim(), day.id).unwrap();
+            todo.set_parent(db, parents.last().copied()).unwrap();
+            parents.push(todo.id);
+            day.add_todo(db, todo).unwrap();
+        }
+        day
+    }
+
+    fn outline(day: &Day) -> Vec<String> {
+        (0..day.todos.len())
+            .map(|i| format!("{}{}", "  ".repeat(day.depth(i)), day.todos[i].text))
+            .collect()
+    }
+
+    /// The outline in memory, checked against the one stored.
+    fn saved(db: &Connection, day: &Day) -> Vec<String> {
+        let shown = outline(day);
+        assert_eq!(shown, outline(&Day::get(db, day.id).unwrap()));
+        shown
+    }
+
+    fn open() -> Connection {
+        init_connection(Path::new(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn indent_under_the_previous_sibling() {
+        let db = open();
+        let mut day = day(&db, &["a", "b", "c"]);
+        day.indent(&db, 0).unwrap();
+        assert_eq!(saved(&db, &day), ["a", "b", "c"]);
+        day.indent(&db, 1).unwrap();
+        day.indent(&db, 2).unwrap();
+        assert_eq!(saved(&db, &day), ["a", "  b", "  c"]);
+        day.indent(&db, 2).unwrap();
+        assert_eq!(saved(&db, &day), ["a", "  b", "    c"]);
+    }
+
+    #[test]
+    fn indent_expands_a_folded_parent() {
+        let db = open();
+        let mut day = day(&db, &["a", "  a1", "b"]);
+        day.toggle_collapsed(&db, 0).unwrap();
+        day.indent(&db, 2).unwrap();
+        assert_eq!(saved(&db, &day), ["a", "  a1", "  b"]);
+        assert!(!day.todos[0].collapsed);
+    }
+
+    #[test]
+    fn outdent_the_last_child() {
+        let db = open();
+        let mut day = day(&db, &["a", "  b", "  c", "d"]);
+        assert_eq!(day.outdent(&db, 2).unwrap(), 2);
+        assert_eq!(saved(&db, &day), ["a", "  b", "c", "d"]);
+        assert_eq!(day.outdent(&db, 0).unwrap(), 0);
+        assert_eq!(saved(&db, &day), ["a", "  b", "c", "d"]);
+    }
+
+    #[test]
+    fn outdent_moves_past_the_remaining_siblings() {
+        let db = open();
+        let mut day = day(&db, &["a", "  b", "    b1", "  c", "d"]);
+        assert_eq!(day.outdent(&db, 1).unwrap(), 2);
+        assert_eq!(saved(&db, &day), ["a", "  c", "b", "  b1", "d"]);
+    }
+
+    #[test]
+    fn move_a_parent_with_its_children() {
+        let db = open();
+        let mut day = day(&db, &["a", "  a1", "  a2", "b", "  b1"]);
+        assert_eq!(day.move_subtree(&db, 0, false).unwrap(), 2);
+        assert_eq!(saved(&db, &day), ["b", "  b1", "a", "  a1", "  a2"]);
+        assert_eq!(day.move_subtree(&db, 2, false).unwrap(), 2);
+        assert_eq!(day.move_subtree(&db, 2, true).unwrap(), 0);
+        assert_eq!(saved(&db, &day), ["a", "  a1", "  a2", "b", "  b1"]);
+        assert_eq!(day.move_subtree(&db, 0, true).unwrap(), 0);
+    }
+
+    #[test]
+    fn move_stays_among_siblings() {
+        let db = open();
+        let mut day = day(&db, &["a", "  a1", "  a2", "b"]);
+        assert_eq!(day.move_subtree(&db, 2, false).unwrap(), 2);
+        assert_eq!(day.move_subtree(&db, 1, true).unwrap(), 1);
+        assert_eq!(day.move_subtree(&db, 2, true).unwrap(), 1);
+        assert_eq!(saved(&db, &day), ["a", "  a2", "  a1", "b"]);
+    }
+
+    #[test]
+    fn fold_hides_the_subtree() {
+        let db = open();
+        let mut day = day(&db, &["a", "  a1", "    x", "b"]);
+        assert_eq!(day.display_order(false, None), [0, 1, 2, 3]);
+        day.toggle_collapsed(&db, 0).unwrap();
+        assert_eq!(day.display_order(false, None), [0, 3]);
+        assert!(Day::get(&db, day.id).unwrap().todos[0].collapsed);
+        day.toggle_collapsed(&db, 1).unwrap();
+        day.toggle_collapsed(&db, 0).unwrap();
+        assert_eq!(day.display_order(false, None), [0, 1, 3]);
+        assert_eq!(day.progress(0), (0, 2));
+    }
+}
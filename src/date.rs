@@ -0,0 +1,31 @@
+use chrono::{Duration, Local, NaiveDate};
+
+pub const FORMAT: &str = "%Y-%m-%d";
+
+pub fn today() -> String {
+    Local::today().format(FORMAT).to_string()
+}
+
+/// Parses `YYYY-MM-DD`, `today`, `tomorrow`, `yesterday` or a `+N`/`-N` day
+/// offset from today into a `YYYY-MM-DD` string.
+pub fn parse(input: &str) -> Result<String, String> {
+    let input = input.trim();
+    let today = Local::today().naive_local();
+    let offset = match input {
+        "today" => Some(0),
+        "tomorrow" => Some(1),
+        "yesterday" => Some(-1),
+        _ if input.starts_with('+') || input.starts_with('-') => input.parse::<i64>().ok(),
+        _ => None,
+    };
+    let date = match offset {
+        Some(days) => today + Duration::days(days),
+        None => NaiveDate::parse_from_str(input, FORMAT).map_err(|_| {
+            format!(
+                "invalid date '{}', expected YYYY-MM-DD, today, tomorrow or +N",
+                input
+            )
+        })?,
+    };
+    Ok(date.format(FORMAT).to_string())
+}
@@ -0,0 +1,80 @@
+use crate::{restore_terminal, setup_terminal};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{self, Command},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn editor_command() -> Vec<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_default();
+    let parts: Vec<String> = editor.split_whitespace().map(String::from).collect();
+    if parts.is_empty() {
+        vec![String::from("vi")]
+    } else {
+        parts
+    }
+}
+
+fn run_editor(path: &Path) -> io::Result<()> {
+    let command = editor_command();
+    let status = Command::new(&command[0])
+        .args(&command[1..])
+        .arg(path)
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "{} exited with {}",
+            command[0], status
+        )))
+    }
+}
+
+// Creates a fresh file only the user can read. `create_new` refuses to follow
+// a symlink or reuse a file someone else planted under the same name.
+fn create_temp_file(extension: &str) -> io::Result<(PathBuf, File)> {
+    let dir = std::env::temp_dir();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    for attempt in 0..100u32 {
+        let name = format!("todo-{}-{:x}.{}", process::id(), nanos ^ attempt, extension);
+        let path = dir.join(name);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "cannot create a temporary file",
+    ))
+}
+
+// Opens `text` in $VISUAL/$EDITOR with the TUI suspended and returns the edited text.
+pub fn edit(text: &str, extension: &str) -> io::Result<String> {
+    let (path, mut file) = create_temp_file(extension)?;
+    let written = writeln!(file, "{}", text);
+    drop(file);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&path);
+        return Err(e);
+    }
+    restore_terminal()?;
+    let result = run_editor(&path).and_then(|_| fs::read_to_string(&path));
+    setup_terminal()?;
+    let _ = fs::remove_file(&path);
+    result
+}
@@ -0,0 +1,40 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum Error {
+    Database(rusqlite::Error),
+    Io(io::Error),
+    Config(String),
+    NotFound(String),
+    Parse(String),
+    Unsupported(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Database(e) => write!(f, "database error: {}", e),
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Config(e) => write!(f, "{}", e),
+            Error::NotFound(e) => write!(f, "{}", e),
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::Unsupported(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Database(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
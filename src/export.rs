@@ -0,0 +1,252 @@
+use crate::{
+    database::{DailyTodo, Day, DayShort, Priority},
+    error::{Error, Result},
+};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::{path::Path, str::FromStr};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Json,
+    Csv,
+}
+
+impl Format {
+    /// Picks the format from a file extension.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        extension.parse().map_err(|_| {
+            Error::Parse(format!(
+                "cannot tell the format of {}, expected .md, .json or .csv",
+                path.display()
+            ))
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "md" | "markdown" => Ok(Format::Markdown),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "invalid format '{}', expected markdown, json or csv",
+                s
+            )),
+        }
+    }
+}
+
+/// Everything that is exported, also what a JSON export is read back into.
+#[derive(Serialize, Deserialize)]
+pub struct Export {
+    pub days: Vec<ExportDay>,
+    #[serde(default)]
+    pub daily_todos: Vec<ExportDailyTodo>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportDay {
+    pub date: String,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub todos: Vec<ExportTodo>,
+}
+
+/// A todo in tree order, `depth` being 0 for top-level todos and one more
+/// for each level of sub-tasks.
+#[derive(Serialize, Deserialize)]
+pub struct ExportTodo {
+    #[serde(default)]
+    pub position: usize,
+    #[serde(default)]
+    pub depth: usize,
+    pub text: String,
+    #[serde(default)]
+    pub completed: bool,
+    #[serde(default)]
+    pub priority: String,
+    #[serde(default)]
+    pub due_date: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportDailyTodo {
+    pub text: String,
+    #[serde(default)]
+    pub recurrence: String,
+}
+
+impl Export {
+    /// The days between `from` and `to`, both included and both optional,
+    /// with the daily todo templates.
+    pub fn load(db: &Connection, from: Option<&str>, to: Option<&str>) -> Result<Self> {
+        let mut days = Vec::new();
+        for short in DayShort::get_all(db, None)? {
+            if from.is_some_and(|from| short.date.as_str() < from)
+                || to.is_some_and(|to| short.date.as_str() > to)
+            {
+                continue;
+            }
+            let day = Day::get(db, short.id)?;
+            let todos = day
+                .todos
+                .iter()
+                .enumerate()
+                .map(|(index, todo)| ExportTodo {
+                    position: index,
+                    depth: day.depth(index),
+                    text: todo.text.clone(),
+                    completed: todo.completed,
+                    priority: todo.priority.to_string(),
+                    due_date: todo.due_date.clone(),
+                    tags: todo.tags.clone(),
+                })
+                .collect();
+            days.push(ExportDay {
+                date: day.date,
+                notes: day.notes,
+                todos,
+            });
+        }
+        let daily_todos = DailyTodo::get_all(db)?
+            .into_iter()
+            .map(|todo| ExportDailyTodo {
+                text: todo.text,
+                recurrence: todo.recurrence.to_string(),
+            })
+            .collect();
+        Ok(Self { days, daily_todos })
+    }
+
+    pub fn render(&self, format: Format) -> Result<String> {
+        Ok(match format {
+            Format::Markdown => self.markdown(),
+            Format::Json => {
+                let json = serde_json::to_string_pretty(self)
+                    .map_err(|e| Error::Parse(format!("cannot write JSON: {}", e)))?;
+                json + "\n"
+            }
+            Format::Csv => self.csv(),
+        })
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = String::new();
+        for day in &self.days {
+            out.push_str(&format!("## {}\n\n", day.date));
+            for todo in &day.todos {
+                out.push_str(&format!("{}- {}\n", "  ".repeat(todo.depth), todo.line()));
+            }
+            if !day.todos.is_empty() {
+                out.push('\n');
+            }
+            if !day.notes.trim().is_empty() {
+                out.push_str(day.notes.trim_end());
+                out.push_str("\n\n");
+            }
+        }
+        if !self.daily_todos.is_empty() {
+            out.push_str("## Daily todos\n\n");
+            for todo in &self.daily_todos {
+                out.push_str(&format!("- {}\n", todo.line()));
+            }
+        }
+        out
+    }
+
+    // One row per todo, per day with notes and per daily todo, told apart
+    // by the `kind` column.
+    fn csv(&self) -> String {
+        let mut out = String::from(
+            "kind,date,position,depth,completed,priority,due_date,tags,recurrence,text\n",
+        );
+        let mut row = |fields: [&str; 10]| {
+            let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        };
+        for day in &self.days {
+            for todo in &day.todos {
+                row([
+                    "todo",
+                    &day.date,
+                    &todo.position.to_string(),
+                    &todo.depth.to_string(),
+                    if todo.completed { "1" } else { "0" },
+                    &todo.priority,
+                    todo.due_date.as_deref().unwrap_or(""),
+                    &todo.tags.join(" "),
+                    "",
+                    &todo.text,
+                ]);
+            }
+            if !day.notes.is_empty() {
+                row(["notes", &day.date, "", "", "", "", "", "", "", &day.notes]);
+            }
+        }
+        for (position, todo) in self.daily_todos.iter().enumerate() {
+            let position = position.to_string();
+            row([
+                "daily",
+                "",
+                &position,
+                "",
+                "",
+                "",
+                "",
+                "",
+                &todo.recurrence,
+                &todo.text,
+            ]);
+        }
+        out
+    }
+}
+
+impl ExportTodo {
+    /// The todo as a Markdown checklist item without the leading `- `.
+    pub fn line(&self) -> String {
+        let marker = self.priority.parse().map_or("", Priority::marker);
+        let mut line = format!(
+            "[{}] {}{}",
+            if self.completed { "x" } else { " " },
+            marker,
+            self.text
+        );
+        for tag in &self.tags {
+            line.push_str(&format!(" #{}", tag));
+        }
+        if let Some(due_date) = &self.due_date {
+            line.push_str(&format!(" (due {})", due_date));
+        }
+        line
+    }
+}
+
+impl ExportDailyTodo {
+    /// The template as a Markdown list item without the leading `- `, with
+    /// the repeat rule in parentheses unless it is daily.
+    pub fn line(&self) -> String {
+        match self.recurrence.as_str() {
+            "" | "daily" => self.text.clone(),
+            rule => format!("{} ({})", self.text, rule),
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
@@ -0,0 +1,85 @@
+/// Matches `query` as a case-insensitive subsequence of `text`. Returns a
+/// score, higher is better, and the char indices of `text` that matched.
+/// Runs of consecutive characters and matches at the start of words score
+/// higher, gaps between matched characters cost a little.
+pub fn score(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return None;
+    }
+    let mut positions: Vec<usize> = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut word_start = true;
+    for (index, c) in text.chars().enumerate() {
+        let wanted = match query.get(positions.len()) {
+            Some(wanted) => *wanted,
+            None => break,
+        };
+        if c.to_lowercase().eq(std::iter::once(wanted)) {
+            score += 1;
+            if word_start {
+                score += 3;
+            }
+            match positions.last() {
+                Some(&last) if last + 1 == index => score += 5,
+                Some(&last) => score -= (index - last - 1).min(5) as i64,
+                None => {}
+            }
+            positions.push(index);
+        }
+        word_start = !c.is_alphanumeric();
+    }
+    (positions.len() == query.len()).then_some((score, positions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsequences() {
+        let cases: &[(&str, &str, Option<&[usize]>)] = &[
+            ("buy", "buy milk", Some(&[0, 1, 2])),
+            ("BuY", "buy milk", Some(&[0, 1, 2])),
+            ("bm", "buy milk", Some(&[0, 4])),
+            ("m k", "buy milk", Some(&[4, 7])),
+            ("ab", "xaab", Some(&[1, 3])),
+            ("té", "Été", Some(&[1, 2])),
+            ("é", "Été", Some(&[0])),
+            ("ba", "ab", None),
+            ("xyz", "buy milk", None),
+            ("milks", "milk", None),
+            ("", "buy milk", None),
+            ("  ", "buy milk", None),
+        ];
+        for &(query, text, expected) in cases {
+            let positions = score(query, text).map(|(_, positions)| positions);
+            assert_eq!(positions.as_deref(), expected, "{:?} in {:?}", query, text);
+        }
+    }
+
+    #[test]
+    fn ranks_runs_and_word_starts_higher() {
+        let score = |query, text| score(query, text).unwrap().0;
+        let better_worse = [
+            ("mil", "milk", "m i l"),
+            ("mil", "milk", "mxixl"),
+            ("m", "milk", "lemon"),
+            ("bm", "buy milk", "bump"),
+            ("ab", "a b", "a-------b"),
+        ];
+        for (query, better, worse) in better_worse {
+            assert!(
+                score(query, better) > score(query, worse),
+                "{:?}: {:?} should beat {:?}",
+                query,
+                better,
+                worse
+            );
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use crate::{
+    database::{parse_tags, DailyTodo, Day, NewTodo, Priority},
+    date,
+    error::{Error, Result},
+    export::{Export, ExportDailyTodo, ExportDay, ExportTodo},
+    recurrence::Recurrence,
+    todo_txt::Task,
+};
+use rusqlite::Connection;
+use std::{path::Path, str::FromStr};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    TodoTxt,
+    Json,
+}
+
+impl Format {
+    /// Picks the format from a file extension.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        extension.parse().map_err(|_| {
+            Error::Parse(format!(
+                "cannot tell the format of {}, expected .md, .txt or .json",
+                path.display()
+            ))
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "md" | "markdown" => Ok(Format::Markdown),
+            "txt" | "todo.txt" | "todotxt" => Ok(Format::TodoTxt),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "invalid format '{}', expected markdown, todo.txt or json",
+                s
+            )),
+        }
+    }
+}
+
+/// Reads `input` into days and daily todos. Entries that do not carry a
+/// date of their own go on `date`.
+pub fn parse(input: &str, format: Format, date: &str) -> Result<Export> {
+    match format {
+        Format::Markdown => Ok(markdown(input, date)),
+        Format::TodoTxt => Ok(todo_txt(input, date)),
+        Format::Json => serde_json::from_str(input)
+            .map_err(|e| Error::Parse(format!("invalid JSON export: {}", e))),
+    }
+}
+
+/// A day of the import, looked up or created on first use.
+fn day_mut<'a>(days: &'a mut Vec<ExportDay>, date: &str) -> &'a mut ExportDay {
+    let index = match days.iter().position(|day| day.date == date) {
+        Some(index) => index,
+        None => {
+            days.push(ExportDay {
+                date: String::from(date),
+                notes: String::new(),
+                todos: Vec::new(),
+            });
+            days.len() - 1
+        }
+    };
+    &mut days[index]
+}
+
+enum Section {
+    Day(String),
+    DailyTodos,
+}
+
+// `## YYYY-MM-DD` headings start a day and `## Daily todos` the templates.
+// Checklist items become todos, two spaces or a tab of indentation making a
+// sub-task, and any other text under a dated heading goes to the notes.
+fn markdown(input: &str, date: &str) -> Export {
+    let mut export = Export {
+        days: Vec::new(),
+        daily_todos: Vec::new(),
+    };
+    let mut section = Section::Day(String::from(date));
+    let mut dated = false;
+    let mut notes: Vec<(String, Vec<&str>)> = Vec::new();
+    for line in input.lines() {
+        let trimmed = line.trim();
+        if let Some(heading) = trimmed.strip_prefix('#') {
+            let heading = heading.trim_start_matches('#').trim();
+            if heading.eq_ignore_ascii_case("daily todos") {
+                section = Section::DailyTodos;
+                continue;
+            }
+            if let Ok(date) = date::parse(heading) {
+                section = Section::Day(date);
+                dated = true;
+                continue;
+            }
+        }
+        let item = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "));
+        match (&section, item) {
+            (Section::DailyTodos, Some(item)) => {
+                let item = checkbox(item).map_or(item, |(_, text)| text);
+                export.daily_todos.push(daily_todo(item));
+            }
+            (Section::DailyTodos, None) => {}
+            (Section::Day(date), Some(item)) if checkbox(item).is_some() => {
+                let indent: usize = line
+                    .chars()
+                    .take_while(|c| c.is_whitespace())
+                    .map(|c| if c == '\t' { 2 } else { 1 })
+                    .sum();
+                let day = day_mut(&mut export.days, date);
+                let depth = match day.todos.last() {
+                    Some(last) => (indent / 2).min(last.depth + 1),
+                    None => 0,
+                };
+                let mut todo = checklist_item(item);
+                todo.position = day.todos.len();
+                todo.depth = depth;
+                day.todos.push(todo);
+            }
+            (Section::Day(date), _) if dated => match notes.iter_mut().find(|(d, _)| d == date) {
+                Some((_, lines)) => lines.push(line),
+                None => notes.push((date.clone(), vec![line])),
+            },
+            _ => {}
+        }
+    }
+    for (date, lines) in notes {
+        let text = lines.join("\n");
+        let text = text.trim_matches('\n').trim_end();
+        if !text.trim().is_empty() {
+            day_mut(&mut export.days, &date).notes = String::from(text);
+        }
+    }
+    export
+}
+
+/// Splits `[ ] text` or `[x] text` into the completion and the text.
+fn checkbox(item: &str) -> Option<(bool, &str)> {
+    let completed = match item.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    Some((completed, item[3..].trim()))
+}
+
+// Reads back what `ExportTodo::line` writes:
+// `[x] !!! text #tag (due YYYY-MM-DD)`.
+fn checklist_item(item: &str) -> ExportTodo {
+    let (completed, mut text) = checkbox(item).unwrap_or((false, item));
+    let mut priority = Priority::None;
+    for candidate in [Priority::High, Priority::Medium, Priority::Low] {
+        if let Some(rest) = text.strip_prefix(candidate.marker()) {
+            priority = candidate;
+            text = rest;
+            break;
+        }
+    }
+    let mut due_date = None;
+    if let Some(rest) = text.strip_suffix(')') {
+        if let Some((rest, due)) = rest.rsplit_once(" (due ") {
+            if let Ok(due) = date::parse(due) {
+                due_date = Some(due);
+                text = rest;
+            }
+        }
+    }
+    let (text, tags) = parse_tags(text);
+    ExportTodo {
+        position: 0,
+        depth: 0,
+        text,
+        completed,
+        priority: priority.to_string(),
+        due_date,
+        tags,
+    }
+}
+
+fn daily_todo(item: &str) -> ExportDailyTodo {
+    if let Some(rest) = item.strip_suffix(')') {
+        if let Some((text, rule)) = rest.rsplit_once(" (") {
+            if rule.parse::<Recurrence>().is_ok() {
+                return ExportDailyTodo {
+                    text: String::from(text.trim()),
+                    recurrence: String::from(rule),
+                };
+            }
+        }
+    }
+    ExportDailyTodo {
+        text: String::from(item.trim()),
+        recurrence: String::new(),
+    }
+}
+
+// One task per line, on its creation date or the date it was done.
+fn todo_txt(input: &str, date: &str) -> Export {
+    let mut days = Vec::new();
+    for task in input.lines().filter_map(Task::parse) {
+        let day = day_mut(&mut days, task.date().unwrap_or(date));
+        let mut todo = task.to_todo();
+        todo.position = day.todos.len();
+        day.todos.push(todo);
+    }
+    days.sort_by(|a, b| a.date.cmp(&b.date));
+    Export {
+        days,
+        daily_todos: Vec::new(),
+    }
+}
+
+/// What an import added or skipped, one line per entry.
+pub struct Report {
+    pub lines: Vec<String>,
+    pub added: usize,
+    pub duplicates: usize,
+}
+
+/// Writes `export` into the database. Todos whose text is already on their
+/// day, daily todos that already exist and note lines already in the notes
+/// are skipped. Run it in a transaction that is rolled back for a dry run.
+pub fn apply(db: &Connection, export: &Export) -> Result<Report> {
+    let mut report = Report {
+        lines: Vec::new(),
+        added: 0,
+        duplicates: 0,
+    };
+    for import in &export.days {
+        let date = date::parse(&import.date).map_err(Error::Parse)?;
+        let mut day = Day::for_date(db, &date)?;
+        report.lines.push(day.date.clone());
+        // Todo ids by depth, the last one at each level being the parent of
+        // the next deeper todo.
+        let mut parents: Vec<i64> = Vec::new();
+        for item in &import.todos {
+            parents.truncate(item.depth);
+            let text = item.text.trim();
+            let line = format!("{}{}", "  ".repeat(item.depth), item.line());
+            if let Some(todo) = day
+                .todos
+                .iter()
+                .find(|todo| todo.text.trim().eq_ignore_ascii_case(text))
+            {
+                parents.push(todo.id);
+                report.lines.push(format!("  = {} (already there)", line));
+                report.duplicates += 1;
+                continue;
+            }
+            let due_date = item.due_date.as_deref().map(date::parse).transpose();
+            let id = day.add_new(
+                db,
+                &NewTodo {
+                    text,
+                    completed: item.completed,
+                    priority: item.priority.parse().unwrap_or(Priority::None),
+                    due_date: due_date.map_err(Error::Parse)?.as_deref(),
+                    tags: &item.tags,
+                    parent: parents.last().copied(),
+                },
+            )?;
+            parents.push(id);
+            report.lines.push(format!("  + {}", line));
+            report.added += 1;
+        }
+        let mut notes = Vec::new();
+        for line in import.notes.lines() {
+            if line.trim().is_empty() {
+                notes.push(line);
+            } else if day.notes.lines().any(|l| l.trim() == line.trim()) {
+                report.lines.push(format!("  = ~ {} (already there)", line));
+                report.duplicates += 1;
+            } else {
+                report.lines.push(format!("  + ~ {}", line));
+                report.added += 1;
+                notes.push(line);
+            }
+        }
+        let notes = notes.join("\n");
+        if !notes.trim().is_empty() {
+            if !day.notes.is_empty() && !day.notes.ends_with('\n') {
+                day.notes.push('\n');
+            }
+            day.notes.push_str(notes.trim_matches('\n'));
+            day.set_notes(db)?;
+        }
+    }
+    if !export.daily_todos.is_empty() {
+        report.lines.push(String::from("daily todos"));
+    }
+    let mut existing = DailyTodo::get_all(db)?;
+    for item in &export.daily_todos {
+        let text = item.text.trim();
+        let recurrence: Recurrence = item.recurrence.parse().map_err(Error::Parse)?;
+        if existing
+            .iter()
+            .any(|todo| todo.text.eq_ignore_ascii_case(text))
+        {
+            report
+                .lines
+                .push(format!("  = {} (already there)", item.line()));
+            report.duplicates += 1;
+            continue;
+        }
+        let mut daily_todo = DailyTodo::new(db, text)?;
+        daily_todo.set_recurrence(db, recurrence)?;
+        report.lines.push(format!("  + {}", daily_todo.get_text()));
+        report.added += 1;
+        existing.push(daily_todo);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_checkboxes() {
+        let cases = [
+            ("[ ] buy milk", Some((false, "buy milk"))),
+            ("[x] buy milk", Some((true, "buy milk"))),
+            ("[X]   buy milk  ", Some((true, "buy milk"))),
+            ("[ ]", Some((false, ""))),
+            ("[]buy milk", None),
+            ("[-] buy milk", None),
+            ("buy milk", None),
+            ("[ ", None),
+            ("éé", None),
+        ];
+        for (item, expected) in cases {
+            assert_eq!(checkbox(item), expected, "{:?}", item);
+        }
+    }
+
+    /// Completion, text, priority, due date and tags of an item.
+    type Item<'a> = (bool, &'a str, &'a str, Option<&'a str>, &'a [&'a str]);
+
+    #[test]
+    fn reads_checklist_items() {
+        let cases: &[(&str, Item)] = &[
+            ("[ ] plain", (false, "plain", "none", None, &[])),
+            (
+                "[x] !!! call mom #family (due 2022-09-05)",
+                (true, "call mom", "high", Some("2022-09-05"), &["family"]),
+            ),
+            ("[ ] !! a", (false, "a", "medium", None, &[])),
+            ("[ ] ! a", (false, "a", "low", None, &[])),
+            ("[ ] !!!no space", (false, "!!!no space", "none", None, &[])),
+            (
+                "[ ] see (due soon)",
+                (false, "see (due soon)", "none", None, &[]),
+            ),
+            ("no checkbox", (false, "no checkbox", "none", None, &[])),
+        ];
+        for &(item, (completed, text, priority, due_date, tags)) in cases {
+            let todo = checklist_item(item);
+            assert_eq!(todo.completed, completed, "{:?}", item);
+            assert_eq!(todo.text, text, "{:?}", item);
+            assert_eq!(todo.priority, priority, "{:?}", item);
+            assert_eq!(todo.due_date.as_deref(), due_date, "{:?}", item);
+            assert_eq!(todo.tags, tags, "{:?}", item);
+        }
+    }
+
+    #[test]
+    fn nests_indented_items() {
+        let input = "## 2022-09-01
+    - [ ] indented first
+- [ ] a
+  - [ ] b
+    - [x] c
+\t- [ ] tab
+- [ ] d
+        - [ ] too deep
+* [ ] star
+- not a todo
+";
+        let export = markdown(input, "2022-10-01");
+        assert_eq!(export.days.len(), 1);
+        let todos: Vec<(&str, usize, usize)> = export.days[0]
+            .todos
+            .iter()
+            .map(|todo| (todo.text.as_str(), todo.depth, todo.position))
+            .collect();
+        assert_eq!(
+            todos,
+            [
+                ("indented first", 0, 0),
+                ("a", 0, 1),
+                ("b", 1, 2),
+                ("c", 2, 3),
+                ("tab", 1, 4),
+                ("d", 0, 5),
+                ("too deep", 1, 6),
+                ("star", 0, 7),
+            ]
+        );
+        assert_eq!(export.days[0].notes, "- not a todo");
+    }
+
+    #[test]
+    fn splits_days_notes_and_daily_todos() {
+        let input = "- [ ] undated
+text before any heading
+
+# 2022-09-02
+
+first line
+
+- [ ] dated
+
+second line
+
+## Daily todos
+- stretch (weekdays)
+- [ ] read (not a rule)
+";
+        let export = markdown(input, "2022-10-01");
+        let days: Vec<(&str, &str, usize)> = export
+            .days
+            .iter()
+            .map(|day| (day.date.as_str(), day.notes.as_str(), day.todos.len()))
+            .collect();
+        assert_eq!(
+            days,
+            [
+                ("2022-10-01", "", 1),
+                ("2022-09-02", "first line\n\n\nsecond line", 1)
+            ]
+        );
+        let daily_todos: Vec<(&str, &str)> = export
+            .daily_todos
+            .iter()
+            .map(|todo| (todo.text.as_str(), todo.recurrence.as_str()))
+            .collect();
+        assert_eq!(
+            daily_todos,
+            [("stretch", "weekdays"), ("read (not a rule)", "")]
+        );
+    }
+}
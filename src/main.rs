@@ -1,393 +1,880 @@
+mod cli;
+mod config;
 mod database;
+mod date;
+mod editor;
+mod error;
+mod export;
+mod fuzzy;
+mod import;
+mod recurrence;
+mod screens;
+mod storage;
+mod todo_txt;
 mod ui;
 
-use database::{init_connection, DailyTodo, Day, DayShort, Todo};
-use ui::{daily_todos_screen, new_daily_todo_screen, new_todo_screen, stats_screen, todos_screen};
+use config::{CarryOver, Config, DayRollover};
+use database::{
+    parse_tags, purge_expired, DailyTodo, Day, DayShort, History, Project, ProjectTodo, Scope,
+    Snapshot, Step, Todo, TrashItem,
+};
+use error::{Error, Result};
+use recurrence::Recurrence;
+use screens::{CarryOverScreen, NewTodoScreen, ScreenStack, TodosScreen};
+use storage::Storage;
 
-use chrono::Local;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event::Key, KeyCode, KeyModifiers},
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use rusqlite::Connection;
-use std::io;
-use tui::{backend::Backend, backend::CrosstermBackend, Frame, Terminal};
-
-enum Screen {
-    NewTodo,
-    Todos,
-    Notes,
-    EditNotes,
-    NewDailyTodo,
-    DailyTodos,
-    Stats,
-}
+use std::{
+    io::{self, Stdout},
+    panic, process,
+    time::Duration,
+};
+use tui::{backend::CrosstermBackend, Terminal};
 
 struct DailyTodoList {
     index: usize,
-    input: String,
     list: Vec<DailyTodo>,
 }
 
 impl DailyTodoList {
-    pub fn new(db: &Connection) -> io::Result<Self> {
-        let list = DailyTodo::get_all(db).unwrap();
-        Ok(Self {
-            index: 0,
-            input: String::new(),
-            list,
-        })
+    pub fn new(db: &Connection) -> Result<Self> {
+        let list = DailyTodo::get_all(db)?;
+        Ok(Self { index: 0, list })
     }
 
-    fn swap(&mut self, db: &Connection, index: usize) {
+    fn swap(&mut self, db: &Connection, index: usize) -> Result<()> {
         self.list.swap(self.index, index);
-        DailyTodo::update_positions(db, &self.list).expect("Error: Cannot update positions.")
+        DailyTodo::update_positions(db, &self.list)?;
+        Ok(())
     }
 
-    fn next(&mut self, db: &Connection, modifiers: KeyModifiers) {
+    fn next(&mut self, db: &Connection, modifiers: KeyModifiers) -> Result<()> {
         if !self.list.is_empty() && self.index < self.list.len() - 1 {
             if modifiers == KeyModifiers::SHIFT {
-                self.swap(db, self.index + 1);
+                self.swap(db, self.index + 1)?;
             }
             self.index += 1;
         }
+        Ok(())
     }
 
-    fn previous(&mut self, db: &Connection, modifiers: KeyModifiers) {
+    fn previous(&mut self, db: &Connection, modifiers: KeyModifiers) -> Result<()> {
         if !self.list.is_empty() && self.index > 0 {
             if modifiers == KeyModifiers::SHIFT {
-                self.swap(db, self.index - 1);
+                self.swap(db, self.index - 1)?;
             }
             self.index -= 1;
         }
+        Ok(())
+    }
+
+    fn create(&mut self, db: &Connection, text: &str) -> Result<()> {
+        if !text.trim().is_empty() {
+            let todo = DailyTodo::new(db, text.trim())?;
+            self.list.push(todo);
+        }
+        Ok(())
     }
 
-    fn create(&mut self, db: &Connection) {
-        if !self.input.trim().is_empty() {
-            if let Ok(todo) = DailyTodo::new(db, self.input.trim()) {
-                self.list.push(todo);
+    fn edit(&mut self, db: &Connection, text: &str) -> Result<()> {
+        if let Some(todo) = self.list.get_mut(self.index) {
+            if !text.trim().is_empty() {
+                todo.set_text(db, text.trim())?;
             }
         }
-        self.input.clear();
+        Ok(())
+    }
+
+    fn set_recurrence(&mut self, db: &Connection, recurrence: Recurrence) -> Result<()> {
+        if let Some(todo) = self.list.get_mut(self.index) {
+            todo.set_recurrence(db, recurrence)?;
+        }
+        Ok(())
     }
 
-    fn delete(&mut self, db: &Connection) {
+    fn delete(&mut self, db: &Connection) -> Result<()> {
         if let Some(todo) = self.list.get(self.index) {
-            if todo.delete(db).is_ok() {
-                self.list.remove(self.index);
-                if self.index >= self.list.len() && self.index != 0 {
-                    self.index -= 1;
+            todo.delete(db)?;
+            self.list.remove(self.index);
+            if self.index >= self.list.len() && self.index != 0 {
+                self.index -= 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The projects with the selected one and the selected todo in it. `focus`
+/// tells whether keys act on the project list or on the project's todos.
+struct ProjectList {
+    index: usize,
+    todo_index: usize,
+    focus: ProjectFocus,
+    list: Vec<Project>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ProjectFocus {
+    Projects,
+    Todos,
+}
+
+impl ProjectList {
+    pub fn new(db: &Connection) -> Result<Self> {
+        Ok(Self {
+            index: 0,
+            todo_index: 0,
+            focus: ProjectFocus::Projects,
+            list: Project::get_all(db)?,
+        })
+    }
+
+    /// Reads the projects again, keeping the selection where possible.
+    fn reload(&mut self, db: &Connection) -> Result<()> {
+        self.list = Project::get_all(db)?;
+        self.index = self.index.min(self.list.len().saturating_sub(1));
+        let todos = self.current().map_or(0, |project| project.todos.len());
+        self.todo_index = self.todo_index.min(todos.saturating_sub(1));
+        if todos == 0 {
+            self.focus = ProjectFocus::Projects;
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Option<&Project> {
+        self.list.get(self.index)
+    }
+
+    fn current_mut(&mut self) -> Option<&mut Project> {
+        self.list.get_mut(self.index)
+    }
+
+    fn next(&mut self, db: &Connection, modifiers: KeyModifiers) -> Result<()> {
+        self.step(db, modifiers, 1)
+    }
+
+    fn previous(&mut self, db: &Connection, modifiers: KeyModifiers) -> Result<()> {
+        self.step(db, modifiers, -1)
+    }
+
+    // Moves the selection of the focused list, Shift moves the item along.
+    fn step(&mut self, db: &Connection, modifiers: KeyModifiers, by: isize) -> Result<()> {
+        let shift = modifiers == KeyModifiers::SHIFT;
+        match self.focus {
+            ProjectFocus::Projects => {
+                let target = self.index.wrapping_add_signed(by);
+                if target < self.list.len() {
+                    if shift {
+                        self.list.swap(self.index, target);
+                        Project::update_positions(db, &self.list)?;
+                    }
+                    self.index = target;
+                    self.todo_index = 0;
+                }
+            }
+            ProjectFocus::Todos => {
+                let index = self.todo_index;
+                if let Some(project) = self.current_mut() {
+                    let target = index.wrapping_add_signed(by);
+                    if target < project.todos.len() {
+                        if shift {
+                            project.todos.swap(index, target);
+                            ProjectTodo::update_positions(db, &project.todos)?;
+                        }
+                        self.todo_index = target;
+                    }
                 }
             }
         }
+        Ok(())
+    }
+
+    fn focus(&mut self, focus: ProjectFocus) {
+        if focus == ProjectFocus::Projects || self.current().is_some() {
+            self.focus = focus;
+        }
+    }
+
+    /// Project names are unique regardless of case. `except` is the project
+    /// being renamed, which may keep its name.
+    fn check_name(db: &Connection, name: &str, except: Option<i64>) -> Result<()> {
+        match Project::find_by_name(db, name)? {
+            Some(project) if Some(project.id) != except => Err(Error::Parse(format!(
+                "project '{}' already exists",
+                project.name
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn create(&mut self, db: &Connection, text: &str) -> Result<()> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(());
+        }
+        match self.focus {
+            ProjectFocus::Projects => {
+                Self::check_name(db, text, None)?;
+                self.list.push(Project::new(db, text)?);
+                self.index = self.list.len() - 1;
+                self.todo_index = 0;
+            }
+            ProjectFocus::Todos => {
+                if let Some(project) = self.current_mut() {
+                    project.add_todo(db, text)?;
+                    self.todo_index = project.todos.len() - 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn edit(&mut self, db: &Connection, text: &str) -> Result<()> {
+        let text = text.trim();
+        let index = self.todo_index;
+        match (self.focus, self.list.get_mut(self.index)) {
+            (_, None) => {}
+            _ if text.is_empty() => {}
+            (ProjectFocus::Projects, Some(project)) => {
+                Self::check_name(db, text, Some(project.id))?;
+                project.rename(db, text)?;
+            }
+            (ProjectFocus::Todos, Some(project)) => {
+                if let Some(todo) = project.todos.get_mut(index) {
+                    todo.set_text(db, text)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Text of the selected project or todo, for editing.
+    fn selected_text(&self) -> Option<&str> {
+        let project = self.current()?;
+        match self.focus {
+            ProjectFocus::Projects => Some(&project.name),
+            ProjectFocus::Todos => project.todos.get(self.todo_index).map(|t| t.text.as_str()),
+        }
+    }
+
+    fn toggle(&mut self, db: &Connection) -> Result<()> {
+        let index = self.todo_index;
+        if let Some(todo) = self.current_mut().and_then(|p| p.todos.get_mut(index)) {
+            todo.toggle(db)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, db: &Connection) -> Result<()> {
+        match self.focus {
+            ProjectFocus::Projects => {
+                if self.index < self.list.len() {
+                    self.list.remove(self.index).delete(db)?;
+                    self.index = self.index.min(self.list.len().saturating_sub(1));
+                    self.todo_index = 0;
+                }
+            }
+            ProjectFocus::Todos => {
+                let index = self.todo_index;
+                if let Some(project) = self.current_mut() {
+                    if index < project.todos.len() {
+                        project.todos.remove(index).delete(db)?;
+                        self.todo_index = index.min(project.todos.len().saturating_sub(1));
+                    }
+                }
+            }
+        }
+        Ok(())
     }
 }
 
 struct StatsList {
     index: usize,
     list: Vec<DayShort>,
+    current: Option<Day>,
+    filter: Option<String>,
 }
 
 impl StatsList {
-    pub fn new(db: &Connection) -> io::Result<Self> {
-        let list = DayShort::get_all(db).unwrap();
-        Ok(Self {
-            index: list.len() - 1,
+    pub fn new(db: &Connection) -> Result<Self> {
+        let list = DayShort::get_all(db, None)?;
+        let today = date::today();
+        let mut stats = Self {
+            index: list
+                .iter()
+                .rposition(|day| day.date <= today)
+                .unwrap_or_default(),
             list,
-        })
+            current: None,
+            filter: None,
+        };
+        stats.load_current(db)?;
+        Ok(stats)
+    }
+
+    pub fn update(&mut self, db: &Connection) -> Result<()> {
+        self.list = DayShort::get_all(db, self.filter.as_deref())?;
+        if self.index >= self.list.len() {
+            self.index = self.list.len().saturating_sub(1);
+        }
+        self.load_current(db)
     }
 
-    pub fn update(&mut self, db: &Connection) -> io::Result<()> {
-        self.list = DayShort::get_all(db).unwrap();
+    fn load_current(&mut self, db: &Connection) -> Result<()> {
+        self.current = match self.list.get(self.index) {
+            Some(day) => Some(Day::get(db, day.id)?),
+            None => None,
+        };
         Ok(())
     }
 
-    pub fn get_current(&self, db: &Connection) -> io::Result<Day> {
-        if let Some(day) = self.list.get(self.index) {
-            let day = Day::get(db, day.id).unwrap();
-            Ok(day)
-        } else {
-            Ok(Day {
-                id: 0,
-                count_todos: 0,
-                done_todos: 0,
-                notes: String::new(),
-                date: String::from("0000-00-00"),
-                todos: vec![],
-            })
+    fn select(&mut self, db: &Connection, day_id: i64) -> Result<()> {
+        if let Some(index) = self.list.iter().position(|day| day.id == day_id) {
+            self.index = index;
+            self.load_current(db)?;
         }
+        Ok(())
     }
 
-    fn next(&mut self) {
+    fn next(&mut self, db: &Connection) -> Result<()> {
         if !self.list.is_empty() && self.index < self.list.len() - 1 {
             self.index += 1;
+            self.load_current(db)?;
         }
+        Ok(())
     }
 
-    fn previous(&mut self) {
+    fn previous(&mut self, db: &Connection) -> Result<()> {
         if !self.list.is_empty() && self.index > 0 {
             self.index -= 1;
+            self.load_current(db)?;
         }
+        Ok(())
     }
 }
 
+/// How often the event loop wakes up without input to check the date.
+const TICK_RATE: Duration = Duration::from_secs(1);
+
 pub struct App {
     index: usize,
-    input: String,
-    screen: Screen,
+    clear_screen: bool,
+    status: Option<String>,
+    offered_day: Option<String>,
+    sort_by_priority: bool,
     db: Connection,
+    storage: Storage,
     day: Day,
+    due: Vec<Todo>,
     daily_todos: DailyTodoList,
+    projects: ProjectList,
     stats_list: StatsList,
+    history: History,
+    config: Config,
 }
 
 impl App {
-    fn new(db: Connection) -> Self {
-        let days = DayShort::get_all(&db).unwrap();
-        let day = if !days.is_empty() {
-            let result = days.last().unwrap();
-            Day::get(&db, result.id).unwrap()
-        } else {
-            Day::new(&db, Local::today().format("%Y-%m-%d").to_string().as_str()).unwrap()
+    fn new(db: Connection, storage: Storage, config: Config) -> Result<Self> {
+        let today = date::today();
+        let day = match Day::latest(&db, &today)? {
+            Some(day) if !day.planned && config.day_rollover == DayRollover::Off => day,
+            _ => Day::get_or_create(&db, &today)?,
         };
-        let daily_todos = DailyTodoList::new(&db).unwrap();
-        let stats_list = StatsList::new(&db).unwrap();
-        Self {
-            screen: if day.todos.is_empty() {
-                Screen::NewTodo
-            } else {
-                Screen::Todos
-            },
-            input: String::new(),
+        let due = Todo::get_due(&db, &today, Some(day.id))?;
+        let daily_todos = DailyTodoList::new(&db)?;
+        let projects = ProjectList::new(&db)?;
+        let stats_list = StatsList::new(&db)?;
+        Ok(Self {
             index: 0,
+            clear_screen: false,
+            status: None,
+            offered_day: None,
+            sort_by_priority: false,
             day,
+            due,
             db,
+            storage,
             daily_todos,
+            projects,
             stats_list,
+            history: History::default(),
+            config,
+        })
+    }
+
+    fn new_day(&mut self) -> Result<()> {
+        let new_date = date::today();
+        if new_date != self.day.date {
+            self.day = Day::get_or_create(&self.db, &new_date)?;
+            self.index = 0;
+            self.history.clear();
+            self.update_due()?;
+            self.stats_list.update(&self.db)?;
         }
+        Ok(())
     }
 
-    fn set_screen(&mut self, screen: Screen) {
-        self.screen = screen;
+    /// Applies the carry-over policy when the current day has not been rolled
+    /// over yet. Returns the previous day when the user should be asked which
+    /// of its unfinished todos to carry over.
+    fn roll_over(&mut self) -> Result<Option<Day>> {
+        if self.day.rolled_over {
+            return Ok(None);
+        }
+        let mut previous = match self.day.previous(&self.db)? {
+            Some(previous) if previous.unfinished().next().is_some() => previous,
+            _ => {
+                self.day.set_rolled_over(&self.db)?;
+                return Ok(None);
+            }
+        };
+        if !self.storage.carries_over() {
+            self.day.move_over(&self.db, &mut previous)?;
+            self.stats_list.update(&self.db)?;
+            return Ok(None);
+        }
+        match self.config.carry_over {
+            CarryOver::Ask => return Ok(Some(previous)),
+            CarryOver::Carry => {
+                let todos: Vec<&Todo> = previous.unfinished().collect();
+                self.carry_over(&previous, &todos)?;
+            }
+            CarryOver::Leave => self.carry_over(&previous, &[])?,
+        }
+        Ok(None)
     }
 
-    fn new_day(&mut self) {
-        let new_date = Local::today().format("%Y-%m-%d").to_string();
-        if new_date != self.day.date {
-            self.day = Day::new(&self.db, new_date.as_str()).unwrap();
-            self.index = 0;
+    fn carry_over(&mut self, from: &Day, todos: &[&Todo]) -> Result<()> {
+        self.day.carry_over(&self.db, from, todos)?;
+        self.stats_list.update(&self.db)?;
+        Ok(())
+    }
+
+    fn update_due(&mut self) -> Result<()> {
+        self.due = Todo::get_due(&self.db, &date::today(), Some(self.day.id))?;
+        Ok(())
+    }
+
+    fn display_order(&self) -> Vec<usize> {
+        self.day
+            .display_order(self.sort_by_priority, self.stats_list.filter.as_deref())
+    }
+
+    /// Shows only todos with the tag, on both the todos and stats screens.
+    fn set_filter(&mut self, tag: Option<String>) -> Result<()> {
+        self.stats_list.filter = tag;
+        self.stats_list.update(&self.db)?;
+        self.clamp_index();
+        Ok(())
+    }
+
+    /// The scope of changes to the current day, for the undo history.
+    fn today(&self) -> [Scope; 1] {
+        [Scope::Day(self.day.id)]
+    }
+
+    fn set_tags(&mut self, tags: &[String]) -> Result<()> {
+        self.record("tags", &self.today(), |app| {
+            if let Some(todo) = app.day.todos.get_mut(app.index) {
+                todo.set_tags(&app.db, tags)?;
+                app.stats_list.update(&app.db)?;
+            }
+            Ok(())
+        })
+    }
+
+    // Shift moves the selected todo together with its sub-tasks.
+    fn select(&mut self, modifiers: KeyModifiers, up: bool) -> Result<()> {
+        if self.index >= self.day.todos.len() {
+            return Ok(());
         }
+        if modifiers == KeyModifiers::SHIFT {
+            if self.sort_by_priority {
+                self.status = Some(String::from("turn off the priority sort to move todos"));
+            } else {
+                self.record("move", &self.today(), |app| {
+                    app.index = app.day.move_subtree(&app.db, app.index, up)?;
+                    Ok(())
+                })?;
+            }
+            return Ok(());
+        }
+        let order = self.display_order();
+        let position = order.iter().position(|&i| i == self.index).unwrap_or(0);
+        let target = if up {
+            position.checked_sub(1)
+        } else {
+            Some(position + 1)
+        };
+        if let Some(&index) = target.and_then(|p| order.get(p)) {
+            self.index = index;
+        }
+        Ok(())
     }
 
-    fn swap(&mut self, index: usize) {
-        self.day.todos.swap(self.index, index);
-        Todo::update_positions(&self.db, &self.day.todos).expect("Error: Cannot update positions.")
+    fn next(&mut self, modifiers: KeyModifiers) -> Result<()> {
+        self.select(modifiers, false)
     }
 
-    fn next(&mut self, modifiers: KeyModifiers) {
-        if !self.day.todos.is_empty() && self.index < self.day.todos.len() - 1 {
-            if modifiers == KeyModifiers::SHIFT {
-                self.swap(self.index + 1);
+    fn previous(&mut self, modifiers: KeyModifiers) -> Result<()> {
+        self.select(modifiers, true)
+    }
+
+    fn change_priority(&mut self, modifiers: KeyModifiers) -> Result<()> {
+        self.record("priority", &self.today(), |app| {
+            if let Some(todo) = app.day.todos.get_mut(app.index) {
+                let priority = if modifiers == KeyModifiers::SHIFT {
+                    todo.priority.lower()
+                } else {
+                    todo.priority.raise()
+                };
+                todo.set_priority(&app.db, priority)?;
             }
-            self.index += 1;
+            Ok(())
+        })
+    }
+
+    /// Keeps the selection on a shown todo after todos were removed or
+    /// filtered out, preferring the closest one above.
+    fn clamp_index(&mut self) {
+        let order = self.display_order();
+        if !order.contains(&self.index) {
+            self.index = order
+                .iter()
+                .filter(|&&i| i < self.index)
+                .max()
+                .or_else(|| order.first())
+                .copied()
+                .unwrap_or(0);
         }
     }
 
-    fn previous(&mut self, modifiers: KeyModifiers) {
-        if !self.day.todos.is_empty() && self.index > 0 {
-            if modifiers == KeyModifiers::SHIFT {
-                self.swap(self.index - 1);
+    fn indent(&mut self) -> Result<()> {
+        self.record("indent", &self.today(), |app| {
+            if app.index < app.day.todos.len() {
+                app.day.indent(&app.db, app.index)?;
             }
-            self.index -= 1;
+            Ok(())
+        })
+    }
+
+    fn outdent(&mut self) -> Result<()> {
+        self.record("outdent", &self.today(), |app| {
+            if app.index < app.day.todos.len() {
+                app.index = app.day.outdent(&app.db, app.index)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn fold(&mut self) -> Result<()> {
+        self.record("fold", &self.today(), |app| {
+            if app.index < app.day.todos.len() && app.day.has_children(app.index) {
+                app.day.toggle_collapsed(&app.db, app.index)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn create(&mut self, text: &str) -> Result<()> {
+        let (text, tags) = parse_tags(text);
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.record("create", &self.today(), |app| {
+            let mut todo = Todo::new(&app.db, &text, app.day.id)?;
+            todo.set_tags(&app.db, &tags)?;
+            app.day.add_todo(&app.db, todo)?;
+            app.stats_list.update(&app.db)?;
+            Ok(())
+        })
+    }
+
+    /// Selects a day on the stats screen, dropping the tag filter if the day
+    /// has no todos with the tag.
+    fn show_day(&mut self, day_id: i64) -> Result<()> {
+        if !self.stats_list.list.iter().any(|day| day.id == day_id) {
+            self.set_filter(None)?;
         }
+        self.stats_list.select(&self.db, day_id)
     }
 
-    fn create(&mut self) {
-        if !self.input.trim().is_empty() {
-            if let Ok(todo) = Todo::new(&self.db, self.input.trim(), self.day.id) {
-                self.day
-                    .add_todo(&self.db, todo)
-                    .expect("Error: Cannot add todo, to day.");
-                self.stats_list.update(&self.db).unwrap();
+    /// Moves the selected project todo into the current day.
+    fn pull(&mut self) -> Result<()> {
+        let index = self.projects.todo_index;
+        let id = match self.projects.current().and_then(|p| p.todos.get(index)) {
+            Some(todo) => todo.id,
+            None => return Ok(()),
+        };
+        let scopes = [Scope::Day(self.day.id), Scope::ProjectTodo(id)];
+        self.record("move from project", &scopes, |app| {
+            if let Some(project) = app.projects.list.get_mut(app.projects.index) {
+                project.pull(&app.db, index, &mut app.day)?;
+                app.projects.todo_index = index.min(project.todos.len().saturating_sub(1));
+                app.stats_list.update(&app.db)?;
             }
+            Ok(())
+        })
+    }
+
+    fn edit(&mut self, text: &str) -> Result<()> {
+        let (text, tags) = parse_tags(text);
+        if text.is_empty() {
+            return Ok(());
         }
-        self.input.clear();
+        self.record("edit", &self.today(), |app| {
+            if let Some(todo) = app.day.todos.get_mut(app.index) {
+                todo.set_text(&app.db, &text)?;
+                todo.set_tags(&app.db, &tags)?;
+                app.stats_list.update(&app.db)?;
+            }
+            Ok(())
+        })
     }
 
-    fn delete(&mut self) {
-        if let Some(todo) = self.day.todos.get(self.index) {
-            if todo.delete(&self.db).is_ok() {
-                self.day
-                    .remove_todo(&self.db, self.index)
-                    .expect("Error: Cannot remove todo.");
-                self.stats_list.update(&self.db).unwrap();
-                if self.index >= self.day.todos.len() && self.index != 0 {
-                    self.index -= 1;
-                }
+    fn set_due_date(&mut self, due_date: Option<&str>) -> Result<()> {
+        self.record("due date", &self.today(), |app| {
+            if let Some(todo) = app.day.todos.get_mut(app.index) {
+                todo.set_due_date(&app.db, due_date)?;
             }
+            Ok(())
+        })
+    }
+
+    /// Moves the selected todo and its sub-tasks to another day, creating
+    /// that day if needed.
+    fn schedule(&mut self, date: &str) -> Result<()> {
+        if date == self.day.date || self.index >= self.day.todos.len() {
+            return Ok(());
         }
+        let target = Day::for_date(&self.db, date)?;
+        let scopes = [Scope::Day(self.day.id), Scope::Day(target.id)];
+        self.record("move to another day", &scopes, |app| {
+            let mut target = target;
+            let end = app.day.subtree_end(app.index);
+            app.day.todos[app.index].set_parent(&app.db, None)?;
+            for mut todo in app.day.todos.drain(app.index..end) {
+                todo.move_to(&app.db, target.id)?;
+            }
+            app.day.update_counts(&app.db)?;
+            target.todos = Todo::get_all(&app.db, target.id)?;
+            target.update_counts(&app.db)?;
+            app.update_due()?;
+            app.stats_list.update(&app.db)?;
+            app.clamp_index();
+            Ok(())
+        })
     }
 
-    fn ui<B: Backend>(&self, f: &mut Frame<B>) {
-        match self.screen {
-            Screen::NewTodo => {
-                todos_screen(self, f, false);
-                new_todo_screen(self, f);
+    fn toggle(&mut self) -> Result<()> {
+        self.record("toggle", &self.today(), |app| {
+            if let Some(todo) = app.day.todos.get_mut(app.index) {
+                todo.toggle(&app.db)?;
+                app.day.update_counts(&app.db)?;
+                app.stats_list.update(&app.db)?;
             }
-            Screen::Todos => todos_screen(self, f, true),
-            Screen::Notes => todos_screen(self, f, false),
-            Screen::EditNotes => todos_screen(self, f, false),
-            Screen::NewDailyTodo => {
-                todos_screen(self, f, false);
-                daily_todos_screen(self, f, false);
-                new_daily_todo_screen(self, f);
+            Ok(())
+        })
+    }
+
+    fn delete(&mut self) -> Result<()> {
+        self.record("delete", &self.today(), |app| {
+            if app.index < app.day.todos.len() {
+                app.day.remove_subtree(&app.db, app.index)?;
+                app.stats_list.update(&app.db)?;
+                app.clamp_index();
+            }
+            Ok(())
+        })
+    }
+
+    /// Runs a change to the database as one step of the undo history. The
+    /// change is rolled back if it fails part way.
+    fn record<F>(&mut self, label: &'static str, scopes: &[Scope], change: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        let before = Snapshot::take(&self.db, scopes)?;
+        self.db.execute_batch("SAVEPOINT change")?;
+        match change(self).and_then(|()| Ok(Snapshot::take(&self.db, scopes)?)) {
+            Ok(after) => {
+                self.db.execute_batch("RELEASE change")?;
+                self.history.push(label, before, after);
+                Ok(())
             }
-            Screen::DailyTodos => {
-                todos_screen(self, f, false);
-                daily_todos_screen(self, f, true);
+            Err(e) => {
+                self.db
+                    .execute_batch("ROLLBACK TO change; RELEASE change")?;
+                self.reload()?;
+                Err(e)
             }
-            Screen::Stats => stats_screen(self, f),
         }
     }
-}
-
-fn main() -> io::Result<()> {
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
 
-    let mut path = std::env::current_exe()?;
-    path.pop();
-    path.push("database.sqlite");
-    let db = init_connection(path.to_str().unwrap()).expect("Error: failed to initialize database");
+    fn undo(&mut self) -> Result<()> {
+        self.status = Some(match self.history.undo(&self.db)? {
+            Step::Done(label) => format!("undid {}", label),
+            Step::Nothing => String::from("nothing to undo"),
+            Step::Conflict(label) => format!("cannot undo {}, changed outside the app", label),
+        });
+        self.reload()
+    }
 
-    let mut app = App::new(db);
+    fn redo(&mut self) -> Result<()> {
+        self.status = Some(match self.history.redo(&self.db)? {
+            Step::Done(label) => format!("redid {}", label),
+            Step::Nothing => String::from("nothing to redo"),
+            Step::Conflict(label) => format!("cannot redo {}, changed outside the app", label),
+        });
+        self.reload()
+    }
 
-    enable_raw_mode()?;
-    loop {
-        terminal.draw(|f| app.ui(f))?;
-
-        if let Key(key) = event::read()? {
-            match app.screen {
-                Screen::NewTodo => match key.code {
-                    KeyCode::Esc => {
-                        app.input.clear();
-                        app.set_screen(Screen::Todos);
-                    }
-                    KeyCode::Backspace => {
-                        app.input.pop();
-                    }
-                    KeyCode::Enter => {
-                        app.set_screen(Screen::Todos);
-                        app.create();
-                    }
-                    KeyCode::Char(c) => app.input.push(c),
-                    _ => {}
-                },
-                Screen::Todos => {
-                    if let KeyCode::Char(char) = key.code {
-                        match char.to_ascii_lowercase() {
-                            'q' => break,
-                            'k' => app.previous(key.modifiers),
-                            'j' => app.next(key.modifiers),
-                            'l' => app.set_screen(Screen::Notes),
-                            'x' => {
-                                if let Some(todo) = app.day.todos.get_mut(app.index) {
-                                    todo.toggle(&app.db).expect("Error: Cannot toggle todo.");
-                                    app.day.update_counts(&app.db).unwrap();
-                                    app.stats_list.update(&app.db).unwrap();
-                                }
-                            }
-                            'd' => app.delete(),
-                            'n' => {
-                                if key.modifiers == KeyModifiers::SHIFT {
-                                    app.new_day();
-                                } else {
-                                    app.set_screen(Screen::NewTodo);
-                                }
-                            }
-                            't' => app.set_screen(Screen::DailyTodos),
-                            's' => app.set_screen(Screen::Stats),
-                            _ => {}
-                        }
-                    }
-                }
-                Screen::NewDailyTodo => match key.code {
-                    KeyCode::Esc => {
-                        app.daily_todos.input.clear();
-                        app.set_screen(Screen::DailyTodos);
-                    }
-                    KeyCode::Backspace => {
-                        app.daily_todos.input.pop();
-                    }
-                    KeyCode::Enter => {
-                        app.set_screen(Screen::DailyTodos);
-                        app.daily_todos.create(&app.db);
-                    }
-                    KeyCode::Char(c) => app.daily_todos.input.push(c),
-                    _ => {}
-                },
-                Screen::DailyTodos => match key.code {
-                    KeyCode::Esc => {
-                        app.set_screen(Screen::Todos);
-                    }
-                    KeyCode::Char(c) => match c.to_ascii_lowercase() {
-                        'j' => app.daily_todos.next(&app.db, key.modifiers),
-                        'k' => app.daily_todos.previous(&app.db, key.modifiers),
-                        'n' => app.set_screen(Screen::NewDailyTodo),
-                        'd' => app.daily_todos.delete(&app.db),
-                        _ => {}
-                    },
-                    _ => {}
-                },
-                Screen::Notes => {
-                    if let KeyCode::Char(char) = key.code {
-                        match char.to_ascii_lowercase() {
-                            'q' => break,
-                            'e' => app.set_screen(Screen::EditNotes),
-                            't' => app.set_screen(Screen::DailyTodos),
-                            'h' => app.set_screen(Screen::Todos),
-                            _ => {}
-                        }
-                    }
-                }
-                Screen::EditNotes => match key.code {
-                    KeyCode::Esc => {
-                        app.set_screen(Screen::Notes);
-                        app.day
-                            .set_notes(&app.db)
-                            .expect("Error: Cannot save notes.");
-                    }
-                    KeyCode::Backspace => {
-                        app.day.notes.pop();
-                    }
-                    KeyCode::Enter => app.day.notes.push('\n'),
-                    KeyCode::Char(c) => app.day.notes.push(c),
-                    _ => {}
-                },
-                Screen::Stats => match key.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Char('h') => app.stats_list.previous(),
-                    KeyCode::Char('l') => app.stats_list.next(),
-                    KeyCode::Char('s') | KeyCode::Esc => {
-                        app.input.clear();
-                        app.set_screen(Screen::Todos);
-                    }
-                    _ => {}
-                },
-            }
+    /// Restores or purges an item of the trash. Clears the undo history,
+    /// whose snapshots may hold the item.
+    fn empty_trash_item(&mut self, item: &TrashItem, restore: bool) -> Result<()> {
+        if restore {
+            item.restore(&self.db)?;
+        } else {
+            item.purge(&self.db)?;
         }
+        self.history.clear();
+        self.daily_todos = DailyTodoList::new(&self.db)?;
+        self.reload()
+    }
+
+    /// Reads the current day and the lists shown next to it again.
+    fn reload(&mut self) -> Result<()> {
+        self.day = Day::get(&self.db, self.day.id)?;
+        self.projects.reload(&self.db)?;
+        self.update_due()?;
+        self.stats_list.update(&self.db)?;
+        self.clamp_index();
+        Ok(())
+    }
+
+    fn report(&mut self, error: Error) {
+        self.status = Some(error.to_string());
+    }
+
+    fn save_notes(&mut self, notes: String) -> Result<()> {
+        self.record("notes", &self.today(), |app| {
+            app.day.notes = notes;
+            app.day.set_notes(&app.db)?;
+            Ok(())
+        })
     }
+}
+
+pub fn setup_terminal() -> io::Result<()> {
+    execute!(
+        io::stdout(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
+    enable_raw_mode()
+}
 
+pub fn restore_terminal() -> io::Result<()> {
     disable_raw_mode()?;
     execute!(
-        terminal.backend_mut(),
+        io::stdout(),
         LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )
+}
+
+fn run(args: cli::Args, config: Config) -> Result<()> {
+    let (mut storage, db) = Storage::open(args.db, args.todo_txt, &config)?;
+    if config.trash_days > 0 {
+        purge_expired(&db, config.trash_days)?;
+    }
+
+    if let Some(command) = args.command {
+        cli::run(&db, &storage, command)?;
+        return storage.save(&db);
+    }
+
+    let mut app = App::new(db, storage, config)?;
+    let mut screens = ScreenStack::new(&mut app, Box::new(TodosScreen));
+    if let Some(previous) = app.roll_over()? {
+        screens.push(&mut app, Box::new(CarryOverScreen::new(previous)));
+    } else if app.day.todos.is_empty() {
+        screens.push(&mut app, Box::new(NewTodoScreen::default()));
+    }
+
+    setup_terminal()?;
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        hook(info);
+    }));
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    let result = event_loop(&mut terminal, &mut app, &mut screens);
+    restore_terminal()?;
     terminal.show_cursor()?;
+    result
+}
+
+fn event_loop(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+    screens: &mut ScreenStack,
+) -> Result<()> {
+    loop {
+        terminal.draw(|f| screens.render(app, f))?;
 
-    Ok(())
+        if !event::poll(TICK_RATE)? {
+            screens.tick(app);
+            continue;
+        }
+        let running = match event::read()? {
+            Event::Key(key) => screens.handle_key(app, key),
+            Event::Paste(text) => screens.handle_paste(app, &text),
+            _ => true,
+        };
+        let saved = app.storage.save(&app.db);
+        if !running {
+            return saved;
+        }
+        if let Err(e) = saved {
+            app.status = Some(e.to_string());
+        }
+        if app.clear_screen {
+            terminal.clear()?;
+            app.clear_screen = false;
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match cli::parse(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("todo: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
+    };
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("todo: {}", e);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(args, config) {
+        eprintln!("todo: {}", e);
+        process::exit(1);
+    }
 }
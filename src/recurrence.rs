@@ -0,0 +1,353 @@
+use crate::date;
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use std::{fmt, str::FromStr};
+
+pub const SYNTAX: &str =
+    "daily, weekdays, mon,fri, every N days [from YYYY-MM-DD], monthly D or cron <dom> <mon> <dow>";
+
+const WEEKDAYS: [(&str, &str, Weekday); 7] = [
+    ("mon", "monday", Weekday::Mon),
+    ("tue", "tuesday", Weekday::Tue),
+    ("wed", "wednesday", Weekday::Wed),
+    ("thu", "thursday", Weekday::Thu),
+    ("fri", "friday", Weekday::Fri),
+    ("sat", "saturday", Weekday::Sat),
+    ("sun", "sunday", Weekday::Sun),
+];
+
+/// When a daily todo template is copied into a new day.
+#[derive(Clone, PartialEq)]
+pub enum Recurrence {
+    Daily,
+    Weekdays,
+    Weekly(Vec<Weekday>),
+    EveryDays { days: i64, from: NaiveDate },
+    Monthly(u32),
+    Cron(Cron),
+}
+
+impl Recurrence {
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        match self {
+            Recurrence::Daily => true,
+            Recurrence::Weekdays => date.weekday().number_from_monday() <= 5,
+            Recurrence::Weekly(weekdays) => weekdays.contains(&date.weekday()),
+            Recurrence::EveryDays { days, from } => {
+                date >= *from && (date - *from).num_days() % days == 0
+            }
+            // Days past the end of a short month fall on its last day.
+            Recurrence::Monthly(day) => date.day() == (*day).min(last_day_of_month(date)),
+            Recurrence::Cron(cron) => cron.matches(date),
+        }
+    }
+}
+
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    match NaiveDate::from_ymd_opt(year, month, 1) {
+        Some(next) => (next - Duration::days(1)).day(),
+        None => 31,
+    }
+}
+
+// Only the three letter abbreviation or the full name, so that words like
+// `month` are not taken for a weekday.
+fn parse_weekday(name: &str) -> Option<Weekday> {
+    let name = name.to_lowercase();
+    WEEKDAYS
+        .iter()
+        .find(|(short, full, _)| name == *short || name == *full)
+        .map(|(_, _, day)| *day)
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let invalid = || format!("invalid repeat rule '{}', expected {}", input, SYNTAX);
+        let recurrence = match words.as_slice() {
+            [] | ["daily"] => Recurrence::Daily,
+            ["weekdays"] => Recurrence::Weekdays,
+            ["every", n, "days" | "day", rest @ ..] => {
+                let days: i64 = n.parse().ok().filter(|n| *n > 0).ok_or_else(invalid)?;
+                let from = match rest {
+                    [] => Local::today().naive_local(),
+                    ["from", from] => {
+                        NaiveDate::parse_from_str(from, date::FORMAT).map_err(|_| invalid())?
+                    }
+                    _ => return Err(invalid()),
+                };
+                Recurrence::EveryDays { days, from }
+            }
+            ["monthly", day] | ["monthly", "on", day] => {
+                let day: u32 = day.parse().map_err(|_| invalid())?;
+                if !(1..=31).contains(&day) {
+                    return Err(invalid());
+                }
+                Recurrence::Monthly(day)
+            }
+            ["cron", fields @ ..] => Recurrence::Cron(
+                fields
+                    .join(" ")
+                    .parse()
+                    .map_err(|e| format!("invalid cron rule: {}", e))?,
+            ),
+            _ => Recurrence::Weekly(
+                words
+                    .concat()
+                    .split(',')
+                    .map(parse_weekday)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(invalid)?,
+            ),
+        };
+        Ok(recurrence)
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekdays => write!(f, "weekdays"),
+            Recurrence::Weekly(weekdays) => {
+                let names: Vec<&str> = weekdays
+                    .iter()
+                    .map(|day| WEEKDAYS[day.num_days_from_monday() as usize].0)
+                    .collect();
+                write!(f, "{}", names.join(","))
+            }
+            Recurrence::EveryDays { days, from } => {
+                write!(f, "every {} days from {}", days, from.format(date::FORMAT))
+            }
+            Recurrence::Monthly(day) => write!(f, "monthly {}", day),
+            Recurrence::Cron(cron) => write!(f, "cron {}", cron.source),
+        }
+    }
+}
+
+/// The date part of a cron expression: day of month, month and day of week.
+/// The minute and hour fields of a full five field expression are ignored.
+#[derive(Clone, PartialEq)]
+pub struct Cron {
+    source: String,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn matches(&self, date: NaiveDate) -> bool {
+        if !self.months[date.month() as usize] {
+            return false;
+        }
+        let day = self.days[date.day() as usize];
+        let weekday = self.weekdays[date.weekday().num_days_from_sunday() as usize];
+        // Like cron, a restricted day of month and day of week match either.
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+/// Parses one cron field such as `*`, `1-5`, `*/2` or `1,15` into a table of
+/// the allowed values.
+fn parse_field(field: &str, min: u32, max: u32, names: bool) -> Result<Vec<bool>, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        match (names, parse_weekday(s)) {
+            // Sunday is 7 here so that ranges like `sat-sun` work.
+            (true, Some(day)) => Ok(day.number_from_monday()),
+            _ => s
+                .parse()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("'{}' is not between {} and {}", s, min, max)),
+        }
+    };
+    let mut allowed = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<usize>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step '{}'", step))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => {
+                    let (start, end) = (value(start)?, value(end)?);
+                    if start > end {
+                        return Err(format!("range '{}' runs backwards", range));
+                    }
+                    (start, end)
+                }
+                None => {
+                    let start = value(range)?;
+                    (start, if step > 1 { max } else { start })
+                }
+            },
+        };
+        for n in (start..=end).step_by(step) {
+            allowed[n as usize] = true;
+        }
+    }
+    Ok(allowed)
+}
+
+impl FromStr for Cron {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = input.split_whitespace().collect();
+        let (days, months, weekdays) = match fields.as_slice() {
+            [days, months, weekdays] | [_, _, days, months, weekdays] => (days, months, weekdays),
+            _ => return Err(String::from("expected <dom> <mon> <dow>")),
+        };
+        let mut weekday_table = parse_field(weekdays, 0, 7, true)?;
+        // Both 0 and 7 are Sunday.
+        weekday_table[0] |= weekday_table[7];
+        Ok(Self {
+            source: fields.join(" "),
+            days: parse_field(days, 1, 31, false)?,
+            months: parse_field(months, 1, 12, false)?,
+            weekdays: weekday_table,
+            any_day: *days == "*",
+            any_weekday: *weekdays == "*",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, date::FORMAT).unwrap()
+    }
+
+    fn rule(s: &str) -> Recurrence {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_simple_rules() {
+        assert!(rule("") == Recurrence::Daily);
+        assert!(rule("Daily") == Recurrence::Daily);
+        assert!(rule("weekdays") == Recurrence::Weekdays);
+        assert!(rule("mon,thu") == Recurrence::Weekly(vec![Weekday::Mon, Weekday::Thu]));
+        assert!(rule("Monday, friday") == Recurrence::Weekly(vec![Weekday::Mon, Weekday::Fri]));
+        assert!(rule("monthly 15") == Recurrence::Monthly(15));
+    }
+
+    #[test]
+    fn rejects_words_that_only_start_like_a_weekday() {
+        for input in [
+            "monthly",
+            "month",
+            "mo",
+            "sunny",
+            "tues",
+            "mon,",
+            "monthly 32",
+        ] {
+            assert!(input.parse::<Recurrence>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for input in [
+            "daily",
+            "weekdays",
+            "mon,fri",
+            "every 14 days from 2022-09-02",
+            "monthly 1",
+            "cron 1,15 * *",
+        ] {
+            assert_eq!(rule(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn weekdays_and_weekly() {
+        // 2022-09-02 is a Friday.
+        assert!(rule("weekdays").occurs_on(date("2022-09-02")));
+        assert!(!rule("weekdays").occurs_on(date("2022-09-03")));
+        assert!(rule("sat,sun").occurs_on(date("2022-09-04")));
+        assert!(!rule("sat,sun").occurs_on(date("2022-09-05")));
+    }
+
+    #[test]
+    fn every_n_days() {
+        let every = rule("every 14 days from 2022-09-02");
+        assert!(every.occurs_on(date("2022-09-02")));
+        assert!(every.occurs_on(date("2022-09-16")));
+        assert!(!every.occurs_on(date("2022-09-09")));
+        assert!(!every.occurs_on(date("2022-08-19")));
+        assert!("every 0 days".parse::<Recurrence>().is_err());
+    }
+
+    #[test]
+    fn monthly_falls_on_the_last_day_of_short_months() {
+        let monthly = rule("monthly 31");
+        assert!(monthly.occurs_on(date("2022-02-28")));
+        assert!(monthly.occurs_on(date("2024-02-29")));
+        assert!(!monthly.occurs_on(date("2024-02-28")));
+        assert!(monthly.occurs_on(date("2022-12-31")));
+    }
+
+    #[test]
+    fn cron_fields() {
+        let days = rule("cron 1,15 * *");
+        assert!(days.occurs_on(date("2022-09-15")));
+        assert!(!days.occurs_on(date("2022-09-16")));
+
+        let weekend = rule("cron * * sat-sun");
+        assert!(weekend.occurs_on(date("2022-09-03")));
+        assert!(weekend.occurs_on(date("2022-09-04")));
+        assert!(!weekend.occurs_on(date("2022-09-05")));
+
+        // Minute and hour are ignored, every other month from January.
+        let steps = rule("cron 0 9 1 */2 *");
+        assert!(steps.occurs_on(date("2022-03-01")));
+        assert!(!steps.occurs_on(date("2022-04-01")));
+
+        // A restricted day of month and day of week match either.
+        let either = rule("cron 13 * fri");
+        assert!(either.occurs_on(date("2022-09-13")));
+        assert!(either.occurs_on(date("2022-09-02")));
+        assert!(!either.occurs_on(date("2022-09-05")));
+
+        assert!(rule("cron * * 0").occurs_on(date("2022-09-04")));
+        assert!(rule("cron * * 7").occurs_on(date("2022-09-04")));
+    }
+
+    #[test]
+    fn cron_rejects_invalid_fields() {
+        for input in [
+            "* * sun-tue",
+            "20-10 * *",
+            "32 * *",
+            "* 13 *",
+            "* * */0",
+            "* *",
+            "* * month",
+        ] {
+            assert!(input.parse::<Cron>().is_err(), "{}", input);
+        }
+    }
+}
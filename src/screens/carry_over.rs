@@ -0,0 +1,63 @@
+use super::{Frame, Screen, Transition};
+use crate::{config::Action, database::Day, error::Result, ui::carry_over_screen, App};
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// Asks which unfinished todos of the previous day to bring into the new one.
+pub struct CarryOverScreen {
+    from: Day,
+    selected: Vec<bool>,
+    index: usize,
+}
+
+impl CarryOverScreen {
+    pub fn new(from: Day) -> Self {
+        let selected = vec![true; from.unfinished().count()];
+        Self {
+            from,
+            selected,
+            index: 0,
+        }
+    }
+}
+
+impl Screen for CarryOverScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        carry_over_screen(app, f, &self.from, &self.selected, self.index);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => {
+                app.carry_over(&self.from, &[])?;
+                return Ok(Transition::Pop);
+            }
+            KeyCode::Enter => {
+                let todos: Vec<_> = self
+                    .from
+                    .unfinished()
+                    .zip(&self.selected)
+                    .filter(|(_, selected)| **selected)
+                    .map(|(todo, _)| todo)
+                    .collect();
+                app.carry_over(&self.from, &todos)?;
+                return Ok(Transition::Pop);
+            }
+            _ => {}
+        }
+        match app.config.keys.action(key.code) {
+            Some(Action::Down) if self.index + 1 < self.selected.len() => self.index += 1,
+            Some(Action::Up) if self.index > 0 => self.index -= 1,
+            Some(Action::Toggle) => {
+                if let Some(selected) = self.selected.get_mut(self.index) {
+                    *selected = !*selected;
+                }
+            }
+            _ => {}
+        }
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
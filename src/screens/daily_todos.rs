@@ -0,0 +1,149 @@
+use super::{Frame, Screen, Transition, TrashScreen};
+use crate::{
+    config::Action,
+    error::{Error, Result},
+    recurrence,
+    ui::{daily_todos_screen, input::TextInput, new_todo_screen},
+    App,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+
+pub struct DailyTodosScreen;
+
+impl Screen for DailyTodosScreen {
+    fn render(&self, app: &App, f: &mut Frame, active: bool) {
+        daily_todos_screen(app, f, active);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        if key.code == KeyCode::Esc {
+            return Ok(Transition::Pop);
+        }
+        match app.config.keys.action(key.code) {
+            Some(Action::Down) => app.daily_todos.next(&app.db, key.modifiers)?,
+            Some(Action::Up) => app.daily_todos.previous(&app.db, key.modifiers)?,
+            Some(Action::New) => {
+                return Ok(Transition::Push(Box::new(NewDailyTodoScreen::default())))
+            }
+            Some(Action::Edit) => {
+                if let Some(todo) = app.daily_todos.list.get(app.daily_todos.index) {
+                    return Ok(Transition::Push(Box::new(NewDailyTodoScreen::edit(
+                        &todo.text,
+                    ))));
+                }
+            }
+            Some(Action::Repeat) => {
+                if let Some(todo) = app.daily_todos.list.get(app.daily_todos.index) {
+                    return Ok(Transition::Push(Box::new(RepeatScreen::new(
+                        &todo.recurrence.to_string(),
+                    ))));
+                }
+            }
+            Some(Action::Delete) => app.daily_todos.delete(&app.db)?,
+            Some(Action::Trash) => return Ok(Transition::Push(Box::new(TrashScreen::default()))),
+            _ => {}
+        }
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Default)]
+pub struct NewDailyTodoScreen {
+    input: TextInput,
+    editing: bool,
+}
+
+impl NewDailyTodoScreen {
+    pub fn edit(text: &str) -> Self {
+        Self {
+            input: TextInput::new(text),
+            editing: true,
+        }
+    }
+}
+
+impl Screen for NewDailyTodoScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let title = if self.editing {
+            "Edit Daily TODO"
+        } else {
+            "New Daily TODO"
+        };
+        new_todo_screen(app, f, title, &self.input);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter => {
+                if self.editing {
+                    app.daily_todos.edit(&app.db, self.input.value())?;
+                } else {
+                    app.daily_todos.create(&app.db, self.input.value())?;
+                }
+                return Ok(Transition::Pop);
+            }
+            _ => {
+                self.input.handle_key(key);
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn handle_paste(&mut self, _app: &mut App, text: &str) -> Result<Transition> {
+        self.input.insert_str(text);
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
+
+/// Popup editing the recurrence rule of the selected daily todo.
+pub struct RepeatScreen {
+    input: TextInput,
+}
+
+impl RepeatScreen {
+    pub fn new(rule: &str) -> Self {
+        Self {
+            input: TextInput::new(rule),
+        }
+    }
+}
+
+impl Screen for RepeatScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let title = format!("Repeat ({})", recurrence::SYNTAX);
+        new_todo_screen(app, f, &title, &self.input);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter => {
+                let recurrence = self.input.value().parse().map_err(Error::Parse)?;
+                app.daily_todos.set_recurrence(&app.db, recurrence)?;
+                return Ok(Transition::Pop);
+            }
+            _ => {
+                self.input.handle_key(key);
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn handle_paste(&mut self, _app: &mut App, text: &str) -> Result<Transition> {
+        self.input.insert_str(text);
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,123 @@
+use super::{Frame, Screen, Transition};
+use crate::{
+    config::expand_home,
+    date,
+    error::{Error, Result},
+    export::{Export, Format},
+    ui::{input::TextInput, new_todo_screen},
+    App,
+};
+use chrono::{Duration, NaiveDate};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::{fs, path::Path};
+
+#[derive(Clone, Copy)]
+enum Range {
+    Day,
+    Week,
+    Month,
+    All,
+}
+
+impl Range {
+    fn next(self) -> Self {
+        match self {
+            Range::Day => Range::Week,
+            Range::Week => Range::Month,
+            Range::Month => Range::All,
+            Range::All => Range::Day,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Range::Day => "this day",
+            Range::Week => "last 7 days",
+            Range::Month => "last 30 days",
+            Range::All => "all days",
+        }
+    }
+
+    fn days(self) -> Option<i64> {
+        match self {
+            Range::Day => Some(1),
+            Range::Week => Some(7),
+            Range::Month => Some(30),
+            Range::All => None,
+        }
+    }
+}
+
+/// Writes the shown day, the days up to it or every day to a file whose
+/// extension picks the format.
+pub struct ExportScreen {
+    input: TextInput,
+    date: String,
+    range: Range,
+}
+
+impl ExportScreen {
+    pub fn new(date: &str) -> Self {
+        Self {
+            input: TextInput::new(&format!("~/todo-{}.md", date)),
+            date: String::from(date),
+            range: Range::Day,
+        }
+    }
+
+    fn export(&self, app: &App) -> Result<String> {
+        let path = expand_home(Path::new(self.input.value().trim()));
+        let format = Format::from_path(&path)?;
+        // All days runs to the last one, planned days after the shown one
+        // included.
+        let (from, to) = match self.range.days() {
+            Some(days) => {
+                let to = NaiveDate::parse_from_str(&self.date, date::FORMAT)
+                    .map_err(|_| Error::Parse(format!("invalid date '{}'", self.date)))?;
+                let from = (to - Duration::days(days - 1))
+                    .format(date::FORMAT)
+                    .to_string();
+                (Some(from), Some(self.date.as_str()))
+            }
+            None => (None, None),
+        };
+        let export = Export::load(&app.db, from.as_deref(), to)?;
+        fs::write(&path, export.render(format)?)?;
+        Ok(format!(
+            "exported {} days to {}",
+            export.days.len(),
+            path.display()
+        ))
+    }
+}
+
+impl Screen for ExportScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let title = format!("Export {} | Tab: range, Enter: export", self.range.label());
+        new_todo_screen(app, f, &title, &self.input);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Tab => self.range = self.range.next(),
+            KeyCode::Enter => {
+                app.status = Some(self.export(app)?);
+                return Ok(Transition::Pop);
+            }
+            _ => {
+                self.input.handle_key(key);
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn handle_paste(&mut self, _app: &mut App, text: &str) -> Result<Transition> {
+        self.input.insert_str(text);
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
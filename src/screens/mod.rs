@@ -0,0 +1,181 @@
+mod carry_over;
+mod daily_todos;
+mod export;
+mod new_day;
+mod notes;
+mod projects;
+mod search;
+mod stats;
+mod tags;
+mod todos;
+mod trash;
+
+pub use carry_over::CarryOverScreen;
+pub use daily_todos::DailyTodosScreen;
+pub use export::ExportScreen;
+pub use new_day::start_new_day;
+pub use notes::NotesScreen;
+pub use projects::ProjectsScreen;
+pub use search::SearchScreen;
+pub use stats::StatsScreen;
+pub use tags::TagsScreen;
+pub use todos::{NewTodoScreen, TodosScreen};
+pub use trash::TrashScreen;
+
+use crate::ui::status_bar;
+use crate::{config::DayRollover, date, error::Result, App};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use new_day::NewDayScreen;
+use std::io::Stdout;
+use tui::backend::CrosstermBackend;
+
+/// Ctrl+r redoes what the undo key undid.
+fn is_redo(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+pub type Frame<'a> = tui::Frame<'a, CrosstermBackend<Stdout>>;
+
+pub enum Transition {
+    None,
+    Push(Box<dyn Screen>),
+    Replace(Box<dyn Screen>),
+    Pop,
+    Quit,
+}
+
+pub trait Screen {
+    fn render(&self, app: &App, f: &mut Frame, active: bool);
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition>;
+
+    fn handle_paste(&mut self, _app: &mut App, _text: &str) -> Result<Transition> {
+        Ok(Transition::None)
+    }
+
+    fn on_enter(&mut self, _app: &mut App) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_exit(&mut self, _app: &mut App) -> Result<()> {
+        Ok(())
+    }
+
+    // Modal screens are drawn on top of the screen below them.
+    fn is_modal(&self) -> bool {
+        false
+    }
+}
+
+pub struct ScreenStack {
+    screens: Vec<Box<dyn Screen>>,
+}
+
+impl ScreenStack {
+    pub fn new(app: &mut App, screen: Box<dyn Screen>) -> Self {
+        let mut stack = Self { screens: vec![] };
+        stack.push(app, screen);
+        stack
+    }
+
+    pub fn push(&mut self, app: &mut App, mut screen: Box<dyn Screen>) {
+        if let Err(e) = screen.on_enter(app) {
+            app.report(e);
+        }
+        self.screens.push(screen);
+    }
+
+    fn pop(&mut self, app: &mut App) {
+        if let Some(mut screen) = self.screens.pop() {
+            if let Err(e) = screen.on_exit(app) {
+                app.report(e);
+            }
+        }
+    }
+
+    pub fn render(&self, app: &App, f: &mut Frame) {
+        let top = self.screens.len().saturating_sub(1);
+        let base = self
+            .screens
+            .iter()
+            .rposition(|screen| !screen.is_modal())
+            .unwrap_or(0);
+        for (index, screen) in self.screens.iter().enumerate().skip(base) {
+            screen.render(app, f, index == top);
+        }
+        status_bar(app, f);
+    }
+
+    // Returns false once the application should quit.
+    pub fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> bool {
+        app.status = None;
+        match self.screens.last_mut() {
+            Some(screen) => {
+                let transition = screen.handle_key(app, key);
+                self.apply(app, transition)
+            }
+            None => false,
+        }
+    }
+
+    pub fn handle_paste(&mut self, app: &mut App, text: &str) -> bool {
+        match self.screens.last_mut() {
+            Some(screen) => {
+                let transition = screen.handle_paste(app, text);
+                self.apply(app, transition)
+            }
+            None => false,
+        }
+    }
+
+    /// Called when no input arrived for a while. Moves to the new day once
+    /// the date changed, but only while no popup or editor is open.
+    pub fn tick(&mut self, app: &mut App) {
+        let today = date::today();
+        if today == app.day.date || self.screens.len() != 1 {
+            return;
+        }
+        match app.config.day_rollover {
+            DayRollover::Auto => {
+                let transition = start_new_day(app);
+                self.apply(app, transition);
+            }
+            DayRollover::Ask if app.offered_day.as_ref() != Some(&today) => {
+                app.offered_day = Some(today.clone());
+                self.push(app, Box::new(NewDayScreen::new(today)));
+            }
+            _ => {}
+        }
+    }
+
+    fn apply(&mut self, app: &mut App, transition: Result<Transition>) -> bool {
+        let transition = match transition {
+            Ok(transition) => transition,
+            Err(e) => {
+                app.report(e);
+                return true;
+            }
+        };
+        match transition {
+            Transition::None => {}
+            Transition::Push(screen) => self.push(app, screen),
+            Transition::Replace(screen) => {
+                self.pop(app);
+                self.push(app, screen);
+            }
+            Transition::Pop => {
+                self.pop(app);
+                if self.screens.is_empty() {
+                    return false;
+                }
+            }
+            Transition::Quit => {
+                while !self.screens.is_empty() {
+                    self.pop(app);
+                }
+                return false;
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,46 @@
+use super::{CarryOverScreen, Frame, Screen, Transition};
+use crate::{error::Result, ui::confirm_screen, App};
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// Switches to today's day and offers the carry-over of unfinished todos
+/// when the policy asks for it.
+pub fn start_new_day(app: &mut App) -> Result<Transition> {
+    app.new_day()?;
+    Ok(match app.roll_over()? {
+        Some(previous) => Transition::Push(Box::new(CarryOverScreen::new(previous))),
+        None => Transition::None,
+    })
+}
+
+/// Asks whether to move to the new day after the date changed.
+pub struct NewDayScreen {
+    date: String,
+}
+
+impl NewDayScreen {
+    pub fn new(date: String) -> Self {
+        Self { date }
+    }
+}
+
+impl Screen for NewDayScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let text = format!("It is {} now. Start the new day?", self.date);
+        confirm_screen(app, f, "New day | Enter: start, Esc: stay", &text);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => Ok(Transition::Pop),
+            KeyCode::Enter => Ok(match start_new_day(app)? {
+                Transition::Push(screen) => Transition::Replace(screen),
+                _ => Transition::Pop,
+            }),
+            _ => Ok(Transition::None),
+        }
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,86 @@
+use super::{is_redo, DailyTodosScreen, Frame, ProjectsScreen, Screen, TodosScreen, Transition};
+use crate::{
+    config::Action,
+    editor,
+    error::Result,
+    ui::{text_area::TextArea, todos_screen, Focus},
+    App,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+
+pub struct NotesScreen;
+
+impl Screen for NotesScreen {
+    fn render(&self, app: &App, f: &mut Frame, active: bool) {
+        todos_screen(app, f, if active { Focus::Notes } else { Focus::None });
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        if is_redo(key) {
+            app.redo()?;
+            return Ok(Transition::None);
+        }
+        match app.config.keys.action(key.code) {
+            Some(Action::Quit) => return Ok(Transition::Quit),
+            Some(Action::Undo) => app.undo()?,
+            Some(Action::OpenEditor) => {
+                app.storage.require_database("notes")?;
+                let notes = editor::edit(&app.day.notes, "md");
+                app.clear_screen = true;
+                app.save_notes(notes?.trim_end().to_string())?;
+            }
+            Some(Action::Edit) => {
+                app.storage.require_database("notes")?;
+                return Ok(Transition::Push(Box::new(EditNotesScreen::new(
+                    &app.day.notes,
+                ))));
+            }
+            Some(Action::DailyTodos) => {
+                app.storage.require_database("daily todos")?;
+                return Ok(Transition::Push(Box::new(DailyTodosScreen)));
+            }
+            Some(Action::Projects) => {
+                app.storage.require_database("projects")?;
+                return Ok(Transition::Push(Box::new(ProjectsScreen)));
+            }
+            Some(Action::Left) => return Ok(Transition::Replace(Box::new(TodosScreen))),
+            _ => {}
+        }
+        Ok(Transition::None)
+    }
+}
+
+pub struct EditNotesScreen {
+    editor: TextArea,
+}
+
+impl EditNotesScreen {
+    pub fn new(notes: &str) -> Self {
+        Self {
+            editor: TextArea::new(notes),
+        }
+    }
+}
+
+impl Screen for EditNotesScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        todos_screen(app, f, Focus::EditNotes(&self.editor));
+    }
+
+    fn handle_key(&mut self, _app: &mut App, key: KeyEvent) -> Result<Transition> {
+        if key.code == KeyCode::Esc {
+            return Ok(Transition::Pop);
+        }
+        self.editor.handle_key(key);
+        Ok(Transition::None)
+    }
+
+    fn handle_paste(&mut self, _app: &mut App, text: &str) -> Result<Transition> {
+        self.editor.insert_str(text);
+        Ok(Transition::None)
+    }
+
+    fn on_exit(&mut self, app: &mut App) -> Result<()> {
+        app.save_notes(self.editor.text())
+    }
+}
@@ -0,0 +1,144 @@
+use super::{Frame, Screen, Transition};
+use crate::{
+    config::Action,
+    error::Result,
+    ui::{confirm_screen, input::TextInput, new_todo_screen, projects_screen},
+    App, ProjectFocus,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// Projects on the left and the todos of the selected one on the right.
+pub struct ProjectsScreen;
+
+impl Screen for ProjectsScreen {
+    fn render(&self, app: &App, f: &mut Frame, active: bool) {
+        projects_screen(app, f, active);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter => match app.projects.focus {
+                ProjectFocus::Projects => app.projects.focus(ProjectFocus::Todos),
+                ProjectFocus::Todos => app.pull()?,
+            },
+            _ => {}
+        }
+        match app.config.keys.action(key.code) {
+            Some(Action::Down) => app.projects.next(&app.db, key.modifiers)?,
+            Some(Action::Up) => app.projects.previous(&app.db, key.modifiers)?,
+            Some(Action::Left) => app.projects.focus(ProjectFocus::Projects),
+            Some(Action::Right) => app.projects.focus(ProjectFocus::Todos),
+            Some(Action::Toggle) => app.projects.toggle(&app.db)?,
+            Some(Action::New) => {
+                return Ok(Transition::Push(Box::new(ProjectInputScreen::default())))
+            }
+            Some(Action::Edit) => {
+                if let Some(text) = app.projects.selected_text() {
+                    return Ok(Transition::Push(Box::new(ProjectInputScreen::edit(text))));
+                }
+            }
+            Some(Action::Delete) => match (app.projects.focus, app.projects.current()) {
+                (ProjectFocus::Projects, Some(project)) if !project.todos.is_empty() => {
+                    let screen = DeleteProjectScreen {
+                        name: project.name.clone(),
+                        count: project.todos.len(),
+                    };
+                    return Ok(Transition::Push(Box::new(screen)));
+                }
+                _ => app.projects.delete(&app.db)?,
+            },
+            Some(Action::Projects) => return Ok(Transition::Pop),
+            _ => {}
+        }
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
+
+/// Names a new project or adds a todo to the selected one, depending on
+/// the focused list.
+#[derive(Default)]
+pub struct ProjectInputScreen {
+    input: TextInput,
+    editing: bool,
+}
+
+impl ProjectInputScreen {
+    fn edit(text: &str) -> Self {
+        Self {
+            input: TextInput::new(text),
+            editing: true,
+        }
+    }
+}
+
+impl Screen for ProjectInputScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let title = match (app.projects.focus, self.editing) {
+            (ProjectFocus::Projects, false) => "New Project",
+            (ProjectFocus::Projects, true) => "Rename Project",
+            (ProjectFocus::Todos, false) => "New TODO",
+            (ProjectFocus::Todos, true) => "Edit TODO",
+        };
+        new_todo_screen(app, f, title, &self.input);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter => {
+                if self.editing {
+                    app.projects.edit(&app.db, self.input.value())?;
+                } else {
+                    app.projects.create(&app.db, self.input.value())?;
+                }
+                return Ok(Transition::Pop);
+            }
+            _ => {
+                self.input.handle_key(key);
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn handle_paste(&mut self, _app: &mut App, text: &str) -> Result<Transition> {
+        self.input.insert_str(text);
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
+
+/// Asks before deleting a project that still has todos.
+struct DeleteProjectScreen {
+    name: String,
+    count: usize,
+}
+
+impl Screen for DeleteProjectScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let text = format!("Delete {} and its {} todos?", self.name, self.count);
+        confirm_screen(app, f, "Delete project | Enter: delete, Esc: keep", &text);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => Ok(Transition::Pop),
+            KeyCode::Enter => {
+                app.projects.delete(&app.db)?;
+                Ok(Transition::Pop)
+            }
+            _ => Ok(Transition::None),
+        }
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,125 @@
+use super::{Frame, Screen, StatsScreen, Transition};
+use crate::{
+    database::{Entry, Hit},
+    error::Result,
+    fuzzy,
+    ui::{input::TextInput, search_screen},
+    App,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+
+const MAX_RESULTS: usize = 100;
+
+/// Search over the todos and notes of every day, updated as you type. Tab
+/// switches between fuzzy matching and the full-text index, which supports
+/// `"phrases"` and `prefix*` queries.
+pub struct SearchScreen {
+    input: TextInput,
+    full_text: bool,
+    // Loaded on enter for fuzzy matching.
+    entries: Vec<Entry>,
+    results: Vec<Hit>,
+    index: usize,
+    // Opened from the stats screen, which is then shown again on Enter.
+    from_stats: bool,
+}
+
+impl SearchScreen {
+    pub fn new(from_stats: bool) -> Self {
+        Self {
+            input: TextInput::default(),
+            full_text: false,
+            entries: Vec::new(),
+            results: Vec::new(),
+            index: 0,
+            from_stats,
+        }
+    }
+
+    fn update(&mut self, app: &App) -> Result<()> {
+        let query = self.input.value();
+        self.index = 0;
+        if self.full_text {
+            // The word being typed matches as a prefix.
+            let query = match query.ends_with(char::is_alphanumeric) {
+                true => format!("{}*", query),
+                false => query.to_string(),
+            };
+            self.results = Hit::search(&app.db, &query, MAX_RESULTS)?;
+            return Ok(());
+        }
+        let mut results: Vec<(i64, usize, Vec<usize>)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| {
+                fuzzy::score(query, &entry.text).map(|(score, positions)| (score, i, positions))
+            })
+            .collect();
+        // Entries are newest first, so ties keep recent days on top.
+        results.sort_by_key(|(score, i, _)| (-score, *i));
+        results.truncate(MAX_RESULTS);
+        self.results = results
+            .into_iter()
+            .map(|(_, i, positions)| Hit::from_entry(&self.entries[i], &positions))
+            .collect();
+        Ok(())
+    }
+}
+
+impl Screen for SearchScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let title = if self.full_text {
+            "Search | full text, Tab: fuzzy"
+        } else {
+            "Search | fuzzy, Tab: full text"
+        };
+        search_screen(app, f, title, &self.input, &self.results, self.index);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter => {
+                if let Some(hit) = self.results.get(self.index) {
+                    app.show_day(hit.day_id)?;
+                    return Ok(if self.from_stats {
+                        Transition::Pop
+                    } else {
+                        Transition::Replace(Box::new(StatsScreen))
+                    });
+                }
+            }
+            KeyCode::Down if self.index + 1 < self.results.len() => self.index += 1,
+            KeyCode::Up if self.index > 0 => self.index -= 1,
+            KeyCode::Up | KeyCode::Down => {}
+            KeyCode::Tab => {
+                self.full_text = !self.full_text;
+                self.update(app)?;
+            }
+            _ => {
+                let before = self.input.value().to_string();
+                self.input.handle_key(key);
+                if self.input.value() != before {
+                    self.update(app)?;
+                }
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn handle_paste(&mut self, app: &mut App, text: &str) -> Result<Transition> {
+        self.input.insert_str(text);
+        self.update(app)?;
+        Ok(Transition::None)
+    }
+
+    fn on_enter(&mut self, app: &mut App) -> Result<()> {
+        self.entries = Entry::get_all(&app.db)?;
+        Ok(())
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
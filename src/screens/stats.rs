@@ -0,0 +1,33 @@
+use super::{Frame, Screen, SearchScreen, TagsScreen, Transition};
+use crate::{config::Action, error::Result, ui::stats_screen, App};
+use crossterm::event::{KeyCode, KeyEvent};
+
+pub struct StatsScreen;
+
+impl Screen for StatsScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        stats_screen(app, f);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        if key.code == KeyCode::Esc {
+            return Ok(Transition::Pop);
+        }
+        match app.config.keys.action(key.code) {
+            Some(Action::Quit) => return Ok(Transition::Quit),
+            Some(Action::Left) => app.stats_list.previous(&app.db)?,
+            Some(Action::Right) => app.stats_list.next(&app.db)?,
+            Some(Action::Stats) => return Ok(Transition::Pop),
+            Some(Action::Search) => return Ok(Transition::Push(Box::new(SearchScreen::new(true)))),
+            Some(Action::Filter) => {
+                return Ok(Transition::Push(Box::new(TagsScreen::filter(&app.db)?)))
+            }
+            _ => {}
+        }
+        Ok(Transition::None)
+    }
+
+    fn on_enter(&mut self, app: &mut App) -> Result<()> {
+        app.stats_list.update(&app.db)
+    }
+}
@@ -0,0 +1,94 @@
+use super::{Frame, Screen, Transition};
+use crate::{config::Action, database::Tag, error::Result, ui::picker_screen, App};
+use crossterm::event::{KeyCode, KeyEvent};
+use rusqlite::Connection;
+
+/// Lists the known tags, either to pick the tags of the selected todo or to
+/// pick the tag to filter by.
+pub struct TagsScreen {
+    tags: Vec<String>,
+    // Tags of the selected todo, `None` when picking a filter.
+    selected: Option<Vec<String>>,
+    index: usize,
+}
+
+impl TagsScreen {
+    pub fn assign(db: &Connection, tags: &[String]) -> Result<Self> {
+        Ok(Self {
+            tags: Tag::get_all(db)?.into_iter().map(|tag| tag.name).collect(),
+            selected: Some(tags.to_vec()),
+            index: 0,
+        })
+    }
+
+    pub fn filter(db: &Connection) -> Result<Self> {
+        Ok(Self {
+            tags: Tag::get_all(db)?.into_iter().map(|tag| tag.name).collect(),
+            selected: None,
+            index: 0,
+        })
+    }
+
+    // When filtering the first row clears the filter.
+    fn len(&self) -> usize {
+        self.tags.len() + usize::from(self.selected.is_none())
+    }
+}
+
+impl Screen for TagsScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let (title, items) = match &self.selected {
+            Some(selected) => (
+                "Tags | x: toggle, Esc: close",
+                self.tags
+                    .iter()
+                    .map(|tag| {
+                        let mark = if selected.contains(tag) { "[x]" } else { "[ ]" };
+                        format!("{} #{}", mark, tag)
+                    })
+                    .collect(),
+            ),
+            None => (
+                "Filter by tag | Enter: pick, Esc: close",
+                std::iter::once(String::from("(all todos)"))
+                    .chain(self.tags.iter().map(|tag| format!("#{}", tag)))
+                    .collect(),
+            ),
+        };
+        picker_screen(app, f, title, items, self.index);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter if self.selected.is_none() => {
+                let tag = self.index.checked_sub(1).map(|i| self.tags[i].clone());
+                app.set_filter(tag)?;
+                return Ok(Transition::Pop);
+            }
+            _ => {}
+        }
+        match app.config.keys.action(key.code) {
+            Some(Action::Down) if self.index + 1 < self.len() => self.index += 1,
+            Some(Action::Up) if self.index > 0 => self.index -= 1,
+            Some(Action::Toggle) => {
+                if let (Some(selected), Some(tag)) = (&mut self.selected, self.tags.get(self.index))
+                {
+                    match selected.iter().position(|t| t == tag) {
+                        Some(position) => {
+                            selected.remove(position);
+                        }
+                        None => selected.push(tag.clone()),
+                    }
+                    app.set_tags(selected)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,221 @@
+use super::{
+    is_redo, start_new_day, DailyTodosScreen, ExportScreen, Frame, NotesScreen, ProjectsScreen,
+    Screen, SearchScreen, StatsScreen, TagsScreen, Transition, TrashScreen,
+};
+use crate::{
+    config::Action,
+    date, editor,
+    error::{Error, Result},
+    ui::{input::TextInput, new_todo_screen, todos_screen, Focus},
+    App,
+};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+pub struct TodosScreen;
+
+impl Screen for TodosScreen {
+    fn render(&self, app: &App, f: &mut Frame, active: bool) {
+        todos_screen(app, f, if active { Focus::Todos } else { Focus::None });
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        if is_redo(key) {
+            app.redo()?;
+            return Ok(Transition::None);
+        }
+        match app.config.keys.action(key.code) {
+            Some(Action::Quit) => return Ok(Transition::Quit),
+            Some(Action::Undo) => app.undo()?,
+            Some(Action::Trash) => return Ok(Transition::Push(Box::new(TrashScreen::default()))),
+            Some(Action::Up) => app.previous(key.modifiers)?,
+            Some(Action::Down) => app.next(key.modifiers)?,
+            Some(Action::Right) => return Ok(Transition::Replace(Box::new(NotesScreen))),
+            Some(Action::Toggle) => app.toggle()?,
+            Some(Action::Edit) => {
+                if let Some(todo) = app.day.todos.get(app.index) {
+                    return Ok(Transition::Push(Box::new(NewTodoScreen::edit(
+                        &todo.text_with_tags(),
+                    ))));
+                }
+            }
+            Some(Action::OpenEditor) => {
+                if let Some(todo) = app.day.todos.get(app.index) {
+                    let text = editor::edit(&todo.text_with_tags(), "txt");
+                    app.clear_screen = true;
+                    app.edit(&text?.split_whitespace().collect::<Vec<_>>().join(" "))?;
+                }
+            }
+            Some(Action::Due) => {
+                if let Some(todo) = app.day.todos.get(app.index) {
+                    let screen = if key.modifiers == KeyModifiers::SHIFT {
+                        DateScreen::schedule()
+                    } else {
+                        DateScreen::due(todo.due_date.as_deref().unwrap_or_default())
+                    };
+                    return Ok(Transition::Push(Box::new(screen)));
+                }
+            }
+            Some(Action::Delete) => app.delete()?,
+            Some(Action::Indent) => {
+                app.storage.require_database("sub-tasks")?;
+                app.indent()?
+            }
+            Some(Action::Outdent) => app.outdent()?,
+            Some(Action::Fold) => app.fold()?,
+            Some(Action::Priority) => app.change_priority(key.modifiers)?,
+            Some(Action::Sort) => app.sort_by_priority = !app.sort_by_priority,
+            Some(Action::Tags) => {
+                if let Some(todo) = app.day.todos.get(app.index) {
+                    let screen = TagsScreen::assign(&app.db, &todo.tags)?;
+                    return Ok(Transition::Push(Box::new(screen)));
+                }
+            }
+            Some(Action::Filter) => {
+                return Ok(Transition::Push(Box::new(TagsScreen::filter(&app.db)?)))
+            }
+            Some(Action::New) => {
+                if key.modifiers == KeyModifiers::SHIFT {
+                    return start_new_day(app);
+                } else {
+                    return Ok(Transition::Push(Box::new(NewTodoScreen::default())));
+                }
+            }
+            Some(Action::DailyTodos) => {
+                app.storage.require_database("daily todos")?;
+                return Ok(Transition::Push(Box::new(DailyTodosScreen)));
+            }
+            Some(Action::Search) => {
+                return Ok(Transition::Push(Box::new(SearchScreen::new(false))))
+            }
+            Some(Action::Projects) => {
+                app.storage.require_database("projects")?;
+                return Ok(Transition::Push(Box::new(ProjectsScreen)));
+            }
+            Some(Action::Stats) => return Ok(Transition::Push(Box::new(StatsScreen))),
+            Some(Action::Export) => {
+                return Ok(Transition::Push(Box::new(ExportScreen::new(&app.day.date))))
+            }
+            _ => {}
+        }
+        Ok(Transition::None)
+    }
+}
+
+#[derive(Default)]
+pub struct NewTodoScreen {
+    input: TextInput,
+    editing: bool,
+}
+
+impl NewTodoScreen {
+    pub fn edit(text: &str) -> Self {
+        Self {
+            input: TextInput::new(text),
+            editing: true,
+        }
+    }
+}
+
+impl Screen for NewTodoScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let title = if self.editing {
+            "Edit TODO"
+        } else {
+            "New TODO"
+        };
+        new_todo_screen(app, f, title, &self.input);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter => {
+                if self.editing {
+                    app.edit(self.input.value())?;
+                } else {
+                    app.create(self.input.value())?;
+                }
+                return Ok(Transition::Pop);
+            }
+            _ => {
+                self.input.handle_key(key);
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn handle_paste(&mut self, _app: &mut App, text: &str) -> Result<Transition> {
+        self.input.insert_str(text);
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
+
+/// Popup asking for a date, either the due date of the selected todo or the
+/// day to move it to.
+pub struct DateScreen {
+    input: TextInput,
+    schedule: bool,
+}
+
+impl DateScreen {
+    pub fn due(due_date: &str) -> Self {
+        Self {
+            input: TextInput::new(due_date),
+            schedule: false,
+        }
+    }
+
+    pub fn schedule() -> Self {
+        Self {
+            input: TextInput::default(),
+            schedule: true,
+        }
+    }
+}
+
+impl Screen for DateScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let title = if self.schedule {
+            "Move to day (YYYY-MM-DD, tomorrow, +N)"
+        } else {
+            "Due date (YYYY-MM-DD, tomorrow, +N, empty to clear)"
+        };
+        new_todo_screen(app, f, title, &self.input);
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter => {
+                let input = self.input.value().trim();
+                if self.schedule {
+                    if !input.is_empty() {
+                        app.schedule(&date::parse(input).map_err(Error::Parse)?)?;
+                    }
+                } else if input.is_empty() {
+                    app.set_due_date(None)?;
+                } else {
+                    app.set_due_date(Some(&date::parse(input).map_err(Error::Parse)?))?;
+                }
+                return Ok(Transition::Pop);
+            }
+            _ => {
+                self.input.handle_key(key);
+            }
+        }
+        Ok(Transition::None)
+    }
+
+    fn handle_paste(&mut self, _app: &mut App, text: &str) -> Result<Transition> {
+        self.input.insert_str(text);
+        Ok(Transition::None)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
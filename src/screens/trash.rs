@@ -0,0 +1,92 @@
+use super::{Frame, Screen, Transition};
+use crate::{
+    config::Action,
+    database::{TrashItem, TrashKind},
+    error::Result,
+    ui::{confirm_screen, picker_screen},
+    App,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// Deleted todos and daily todos of all days, to restore or purge.
+#[derive(Default)]
+pub struct TrashScreen {
+    items: Vec<TrashItem>,
+    index: usize,
+    // Asking before the selected item is deleted for good.
+    purging: bool,
+}
+
+impl TrashScreen {
+    fn load(&mut self, app: &App) -> Result<()> {
+        self.items = TrashItem::get_all(&app.db)?;
+        self.index = self.index.min(self.items.len().saturating_sub(1));
+        Ok(())
+    }
+}
+
+impl Screen for TrashScreen {
+    fn render(&self, app: &App, f: &mut Frame, _active: bool) {
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                let from = match &item.kind {
+                    TrashKind::Todo { date, .. } => date.as_str(),
+                    TrashKind::DailyTodo => "daily",
+                };
+                let mut text = format!("{:<10} {}", from, item.text);
+                if let TrashKind::Todo { sub_tasks, .. } = item.kind {
+                    if sub_tasks > 0 {
+                        text.push_str(&format!(" (+{} sub-tasks)", sub_tasks));
+                    }
+                }
+                format!("{} | deleted {}", text, item.deleted_at)
+            })
+            .collect();
+        let title = "Trash | Enter: restore, d: delete for good, Esc: close";
+        picker_screen(app, f, title, items, self.index);
+        if let (true, Some(item)) = (self.purging, self.items.get(self.index)) {
+            let text = format!("Delete '{}' for good?", item.text);
+            confirm_screen(app, f, "Trash | Enter: delete, Esc: keep", &text);
+        }
+    }
+
+    fn handle_key(&mut self, app: &mut App, key: KeyEvent) -> Result<Transition> {
+        if self.purging {
+            self.purging = false;
+            if let (KeyCode::Enter, Some(item)) = (key.code, self.items.get(self.index)) {
+                app.empty_trash_item(item, false)?;
+                self.load(app)?;
+            }
+            return Ok(Transition::None);
+        }
+        match key.code {
+            KeyCode::Esc => return Ok(Transition::Pop),
+            KeyCode::Enter => {
+                if let Some(item) = self.items.get(self.index) {
+                    app.empty_trash_item(item, true)?;
+                    self.load(app)?;
+                }
+                return Ok(Transition::None);
+            }
+            _ => {}
+        }
+        match app.config.keys.action(key.code) {
+            Some(Action::Down) if self.index + 1 < self.items.len() => self.index += 1,
+            Some(Action::Up) if self.index > 0 => self.index -= 1,
+            Some(Action::Delete) => self.purging = !self.items.is_empty(),
+            Some(Action::Trash) => return Ok(Transition::Pop),
+            _ => {}
+        }
+        Ok(Transition::None)
+    }
+
+    fn on_enter(&mut self, app: &mut App) -> Result<()> {
+        self.load(app)
+    }
+
+    fn is_modal(&self) -> bool {
+        true
+    }
+}
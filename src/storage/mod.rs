@@ -0,0 +1,83 @@
+pub mod todo_txt;
+
+use crate::{
+    config::{self, Backend, Config},
+    database::init_connection,
+    error::{Error, Result},
+};
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use todo_txt::TodoTxt;
+
+/// Where todos are kept. Everything works on a database connection, with
+/// todo.txt that is an in-memory database filled from the files and saved
+/// back to them.
+pub enum Storage {
+    Sqlite,
+    TodoTxt(Box<TodoTxt>),
+}
+
+impl Storage {
+    // Resolution order: --todo-txt, --db, TODO_TXT, then the config file,
+    // which falls back to the SQLite database.
+    pub fn open(
+        db: Option<PathBuf>,
+        todo_txt: Option<PathBuf>,
+        config: &Config,
+    ) -> Result<(Self, Connection)> {
+        let todo_txt =
+            match (todo_txt, &db) {
+                (Some(path), _) => Some(path),
+                (None, Some(_)) => None,
+                (None, None) => std::env::var_os("TODO_TXT").map(PathBuf::from).or_else(|| {
+                    match config.storage {
+                        Backend::Sqlite => None,
+                        Backend::TodoTxt => Some(
+                            config
+                                .todo_txt
+                                .clone()
+                                .unwrap_or_else(|| PathBuf::from("~/todo.txt")),
+                        ),
+                    }
+                }),
+            };
+        match todo_txt {
+            Some(path) => {
+                let db = init_connection(Path::new(":memory:"))?;
+                let storage = TodoTxt::load(&db, config::expand_home(&path))?;
+                Ok((Storage::TodoTxt(Box::new(storage)), db))
+            }
+            None => {
+                let path = config::database_path(db, config)?;
+                Ok((Storage::Sqlite, init_connection(&path)?))
+            }
+        }
+    }
+
+    /// Whether unfinished todos are copied into a new day. A todo.txt task
+    /// stays on its line until it is done, so it is moved along instead of
+    /// becoming a new line for every day it is left open.
+    pub fn carries_over(&self) -> bool {
+        matches!(self, Storage::Sqlite)
+    }
+
+    /// Fails for `what` when the storage has no place for it, rather than
+    /// losing it once the app exits.
+    pub fn require_database(&self, what: &str) -> Result<()> {
+        match self {
+            Storage::Sqlite => Ok(()),
+            Storage::TodoTxt(_) => Err(Error::Unsupported(format!(
+                "{} cannot be kept in a todo.txt file, use the SQLite database",
+                what
+            ))),
+        }
+    }
+
+    /// Writes changes through to the files, SQLite has them already.
+    pub fn save(&mut self, db: &Connection) -> Result<()> {
+        match self {
+            Storage::Sqlite => Ok(()),
+            Storage::TodoTxt(storage) => storage.save(db),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+use crate::{
+    database::{Day, DayShort, NewTodo, Priority, Todo},
+    date::today,
+    error::Result,
+    todo_txt::Task,
+};
+use rusqlite::Connection;
+use std::{
+    collections::HashMap,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum File {
+    Todo,
+    Done,
+}
+
+/// A todo as it was read, so that it is written back unchanged, in its
+/// place, as long as it is not edited.
+struct Line {
+    file: File,
+    index: usize,
+    date: String,
+    task: Task,
+    text: String,
+}
+
+/// Todos kept in a todo.txt file and the done.txt next to it. The files are
+/// read into an in-memory database and written back after every change.
+pub struct TodoTxt {
+    todo: PathBuf,
+    done: PathBuf,
+    lines: HashMap<i64, Line>,
+    // Lines that are not tasks, blank ones included, kept in their place.
+    kept: Vec<(File, usize, String)>,
+    // The files as last read or written, to notice changes made by others.
+    on_disk: (String, String),
+    // What the database rendered to when the files were last in sync.
+    written: (String, String),
+}
+
+fn read(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => {
+            Ok(result
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?)
+        }
+    }
+}
+
+// Writes a temporary file next to `path` and renames it over, so a crash or
+// a full disk never leaves half a file behind.
+fn write(path: &Path, content: &str) -> Result<()> {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("todo.txt");
+    let temp = path.with_file_name(format!(".{}.tmp", name));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        file.set_permissions(metadata.permissions())?;
+    }
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+impl TodoTxt {
+    pub fn load(db: &Connection, todo: PathBuf) -> Result<Self> {
+        let done = todo.with_file_name("done.txt");
+        let mut storage = Self {
+            todo,
+            done,
+            lines: HashMap::new(),
+            kept: Vec::new(),
+            on_disk: (String::new(), String::new()),
+            written: (String::new(), String::new()),
+        };
+        let today = today();
+        let tx = db.unchecked_transaction()?;
+        for (file, path) in [(File::Todo, &storage.todo), (File::Done, &storage.done)] {
+            let content = read(path)?;
+            for (index, text) in content.lines().enumerate() {
+                let task = match Task::parse(text) {
+                    Some(task) => task,
+                    None => {
+                        storage.kept.push((file, index, String::from(text)));
+                        continue;
+                    }
+                };
+                // Open tasks are on today's list unless they wait for a later
+                // day, done ones on the day they were done.
+                let date = if task.completed {
+                    task.completed_on.as_deref().or(task.created_on.as_deref())
+                } else {
+                    task.threshold.as_deref().filter(|t| *t > today.as_str())
+                };
+                let date = String::from(date.unwrap_or(&today));
+                let mut day = Day::for_date(&tx, &date)?;
+                let id = day.add_new(
+                    &tx,
+                    &NewTodo {
+                        text: &task.text,
+                        completed: task.completed,
+                        priority: task.priority(),
+                        due_date: task.due_date.as_deref(),
+                        tags: &task.tags,
+                        parent: None,
+                    },
+                )?;
+                let line = Line {
+                    file,
+                    index,
+                    date,
+                    task,
+                    text: String::from(text),
+                };
+                storage.lines.insert(id, line);
+            }
+            match file {
+                File::Todo => storage.on_disk.0 = content,
+                File::Done => storage.on_disk.1 = content,
+            }
+        }
+        tx.commit()?;
+        storage.written = storage.render(db)?;
+        Ok(storage)
+    }
+
+    /// Writes the files whose todos changed. Refuses, leaving both files
+    /// alone, when one of them was changed by someone else since it was
+    /// read, a `git pull` for instance.
+    pub fn save(&mut self, db: &Connection) -> Result<()> {
+        let (todo, done) = self.render(db)?;
+        let files = [
+            (&self.todo, todo != self.written.0, &self.on_disk.0),
+            (&self.done, done != self.written.1, &self.on_disk.1),
+        ];
+        for (path, changed, on_disk) in files {
+            if changed && read(path)? != *on_disk {
+                return Err(io::Error::other(format!(
+                    "{} changed on disk, restart todo to load it before making changes",
+                    path.display()
+                ))
+                .into());
+            }
+        }
+        if todo != self.written.0 {
+            write(&self.todo, &todo)?;
+            self.on_disk.0 = todo.clone();
+            self.written.0 = todo;
+        }
+        if done != self.written.1 {
+            write(&self.done, &done)?;
+            self.on_disk.1 = done.clone();
+            self.written.1 = done;
+        }
+        Ok(())
+    }
+
+    // Todos read from the files keep their order, new ones follow in the
+    // order of their days. Completed todos only stay in done.txt when they
+    // came from there.
+    fn render(&self, db: &Connection) -> Result<(String, String)> {
+        let today = today();
+        let mut rows = Vec::new();
+        for day in DayShort::get_all(db, None)? {
+            for todo in Todo::get_all(db, day.id)? {
+                let line = self.lines.get(&todo.id);
+                let task = self.task(&todo, &day.date, &today, line);
+                let text = match line {
+                    Some(line) if line.task == task => line.text.clone(),
+                    _ => task.to_string(),
+                };
+                let file = match line {
+                    Some(line) if line.file == File::Done && task.completed => File::Done,
+                    _ => File::Todo,
+                };
+                let index = line.map_or(usize::MAX, |line| line.index);
+                rows.push((file, index, text));
+            }
+        }
+        for (file, index, text) in &self.kept {
+            rows.push((*file, *index, text.clone()));
+        }
+        rows.sort_by_key(|(file, index, _)| (*file, *index));
+        let (mut todo, mut done) = (String::new(), String::new());
+        for (file, _, text) in rows {
+            let out = if file == File::Done {
+                &mut done
+            } else {
+                &mut todo
+            };
+            out.push_str(&text);
+            out.push('\n');
+        }
+        Ok((todo, done))
+    }
+
+    fn task(&self, todo: &Todo, date: &str, today: &str, line: Option<&Line>) -> Task {
+        let mut task = line.map_or_else(Task::default, |line| line.task.clone());
+        // New, or moved to another day.
+        let moved = line.is_none_or(|line| line.date != date);
+        if line.is_none() {
+            task.created_on = Some(String::from(date.min(today)));
+        }
+        if todo.completed {
+            if !task.completed || moved {
+                task.completed_on = Some(String::from(date));
+            }
+        } else {
+            task.completed_on = None;
+            if moved {
+                task.threshold = if date > today {
+                    Some(String::from(date))
+                } else {
+                    task.threshold.filter(|t| t.as_str() <= today)
+                };
+            }
+        }
+        task.completed = todo.completed;
+        if task.priority() != todo.priority {
+            task.priority = match todo.priority {
+                Priority::None => None,
+                Priority::Low => Some('C'),
+                Priority::Medium => Some('B'),
+                Priority::High => Some('A'),
+            };
+        }
+        task.text = todo.text.clone();
+        task.contexts.retain(|context| todo.tags.contains(context));
+        task.tags = todo.tags.clone();
+        task.due_date = todo.due_date.clone();
+        task
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::init_connection, date};
+
+    struct Dir(PathBuf);
+
+    impl Dir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("todo-test-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for Dir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const TODO: &str = "(A) 2022-09-01 call mom +family @phone due:2022-09-05
+
+# not a task
++someday @maybe
+2022-09-01   spaced   out
+x 2022-09-02 2022-09-01 written back as is
+";
+    const DONE: &str = "x 2022-08-30 2022-08-29 pay rent +home pri:B\n\n";
+
+    fn open(dir: &Dir) -> (Connection, TodoTxt) {
+        let db = init_connection(Path::new(":memory:")).unwrap();
+        let storage = TodoTxt::load(&db, dir.0.join("todo.txt")).unwrap();
+        (db, storage)
+    }
+
+    #[test]
+    fn save_without_changes_keeps_the_files() {
+        let dir = Dir::new("unchanged");
+        fs::write(dir.0.join("todo.txt"), TODO).unwrap();
+        fs::write(dir.0.join("done.txt"), DONE).unwrap();
+        let (db, mut storage) = open(&dir);
+        storage.save(&db).unwrap();
+        assert_eq!(storage.render(&db).unwrap(), (TODO.into(), DONE.into()));
+        assert_eq!(fs::read_to_string(dir.0.join("todo.txt")).unwrap(), TODO);
+        assert_eq!(fs::read_to_string(dir.0.join("done.txt")).unwrap(), DONE);
+    }
+
+    #[test]
+    fn save_rewrites_only_edited_lines() {
+        let dir = Dir::new("edited");
+        fs::write(dir.0.join("todo.txt"), TODO).unwrap();
+        let (db, mut storage) = open(&dir);
+        let id = *storage
+            .lines
+            .iter()
+            .find(|(_, line)| line.text.contains("spaced"))
+            .unwrap()
+            .0;
+        Todo::get(&db, id).unwrap().set_text(&db, "tidy").unwrap();
+        storage.save(&db).unwrap();
+        let expected = TODO.replace("2022-09-01   spaced   out", "2022-09-01 tidy");
+        assert_eq!(
+            fs::read_to_string(dir.0.join("todo.txt")).unwrap(),
+            expected
+        );
+        assert!(!dir.0.join(".todo.txt.tmp").exists());
+    }
+
+    #[test]
+    fn save_refuses_to_overwrite_outside_changes() {
+        let dir = Dir::new("conflict");
+        let path = dir.0.join("todo.txt");
+        fs::write(&path, TODO).unwrap();
+        let (db, mut storage) = open(&dir);
+        let pulled = format!("{}2022-09-03 pulled from elsewhere\n", TODO);
+        fs::write(&path, &pulled).unwrap();
+        let id = *storage.lines.keys().next().unwrap();
+        Todo::get(&db, id).unwrap().set_text(&db, "edited").unwrap();
+        assert!(storage.save(&db).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), pulled);
+    }
+
+    fn texts(db: &Connection, date: &str) -> Vec<String> {
+        Day::find_by_date(db, date)
+            .unwrap()
+            .map_or_else(Vec::new, |day| {
+                day.todos.into_iter().map(|todo| todo.text).collect()
+            })
+    }
+
+    #[test]
+    fn load_open_tasks_into_today() {
+        let dir = Dir::new("days");
+        let content = "2022-09-01 open since september
+(A) undated
+wait for it t:2099-01-05
+past threshold t:2022-09-03
+x 2022-09-02 2022-09-01 done in september
+x 2022-09-04 done without a creation date
+";
+        fs::write(dir.0.join("todo.txt"), content).unwrap();
+        let (db, mut storage) = open(&dir);
+        assert_eq!(
+            texts(&db, &today()),
+            ["open since september", "undated", "past threshold"]
+        );
+        assert_eq!(texts(&db, "2099-01-05"), ["wait for it"]);
+        assert_eq!(texts(&db, "2022-09-01"), Vec::<String>::new());
+        assert_eq!(texts(&db, "2022-09-02"), ["done in september"]);
+        assert_eq!(texts(&db, "2022-09-04"), ["done without a creation date"]);
+        storage.save(&db).unwrap();
+        assert_eq!(storage.render(&db).unwrap().0, content);
+    }
+
+    #[test]
+    fn schedule_later_days_with_a_threshold() {
+        let dir = Dir::new("threshold");
+        fs::write(dir.0.join("todo.txt"), "2022-09-01 move me\n").unwrap();
+        let (db, mut storage) = open(&dir);
+        let today = today();
+        let tomorrow = date::parse("tomorrow").unwrap();
+        let mut day = Day::for_date(&db, &today).unwrap();
+        let mut later = Day::for_date(&db, &tomorrow).unwrap();
+        day.todos[0].move_to(&db, later.id).unwrap();
+        let todo = Todo::new(&db, "later", later.id).unwrap();
+        later.add_todo(&db, todo).unwrap();
+        let mut todo = Todo::new(&db, "now", day.id).unwrap();
+        todo.set_completed(&db, true).unwrap();
+        storage.save(&db).unwrap();
+        let expected = format!(
+            "2022-09-01 move me t:{tomorrow}\nx {today} {today} now\n{today} later t:{tomorrow}\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.0.join("todo.txt")).unwrap(),
+            expected
+        );
+
+        let (db, _) = open(&dir);
+        assert_eq!(texts(&db, &tomorrow), ["move me", "later"]);
+        assert_eq!(texts(&db, &today), ["now"]);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{database::Priority, date, export::ExportTodo};
+use chrono::NaiveDate;
+use std::fmt;
+
+/// A line of a todo.txt file:
+/// `x (A) 2022-09-02 2022-09-01 text +project @context due:2022-09-05`, where
+/// `t:2022-09-03` holds an open task back until that day.
+/// Projects and contexts are both tags here, contexts are remembered so a
+/// rewritten line keeps its `@`.
+#[derive(Clone, Default, PartialEq)]
+pub struct Task {
+    pub completed: bool,
+    pub priority: Option<char>,
+    pub completed_on: Option<String>,
+    pub created_on: Option<String>,
+    pub text: String,
+    pub tags: Vec<String>,
+    pub contexts: Vec<String>,
+    pub due_date: Option<String>,
+    pub threshold: Option<String>,
+}
+
+impl Task {
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut task = Task::default();
+        let mut words = line.split_whitespace().peekable();
+        task.completed = words.next_if_eq(&"x").is_some();
+        task.priority = words
+            .next_if(|w| is_priority(w))
+            .and_then(|w| w.chars().nth(1));
+        // A completed task's first date is when it was done, the second when
+        // it was created.
+        let first = words.next_if(|w| is_date(w)).map(String::from);
+        if task.completed {
+            task.completed_on = first;
+            task.created_on = words.next_if(|w| is_date(w)).map(String::from);
+        } else {
+            task.created_on = first;
+        }
+        let mut text = Vec::new();
+        for word in words {
+            if let Some(due) = word.strip_prefix("due:").filter(|d| is_date(d)) {
+                task.due_date = Some(String::from(due));
+            } else if let Some(threshold) = word.strip_prefix("t:").filter(|t| is_date(t)) {
+                task.threshold = Some(String::from(threshold));
+            } else if let Some(pri) = word.strip_prefix("pri:").filter(|p| p.len() == 1) {
+                task.priority = pri.chars().next().filter(char::is_ascii_uppercase);
+            } else if word.len() > 1 && (word.starts_with('+') || word.starts_with('@')) {
+                let name = word[1..].to_lowercase();
+                if word.starts_with('@') && !task.contexts.contains(&name) {
+                    task.contexts.push(name.clone());
+                }
+                if !task.tags.contains(&name) {
+                    task.tags.push(name);
+                }
+            } else {
+                text.push(word);
+            }
+        }
+        if text.is_empty() {
+            return None;
+        }
+        task.text = text.join(" ");
+        Some(task)
+    }
+
+    /// The day the task belongs to, its creation date or else the date it
+    /// was done.
+    pub fn date(&self) -> Option<&str> {
+        self.created_on.as_deref().or(self.completed_on.as_deref())
+    }
+
+    // A and B map to the two highest priorities, everything below to low.
+    pub fn priority(&self) -> Priority {
+        match self.priority {
+            None => Priority::None,
+            Some('A') => Priority::High,
+            Some('B') => Priority::Medium,
+            Some(_) => Priority::Low,
+        }
+    }
+
+    pub fn to_todo(&self) -> ExportTodo {
+        ExportTodo {
+            position: 0,
+            depth: 0,
+            text: self.text.clone(),
+            completed: self.completed,
+            priority: self.priority().to_string(),
+            due_date: self.due_date.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Task {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.completed {
+            write!(f, "x ")?;
+            if let Some(done) = &self.completed_on {
+                write!(f, "{} ", done)?;
+            }
+        } else if let Some(priority) = self.priority {
+            write!(f, "({}) ", priority)?;
+        }
+        if let Some(created) = &self.created_on {
+            write!(f, "{} ", created)?;
+        }
+        write!(f, "{}", self.text)?;
+        for tag in &self.tags {
+            let sigil = if self.contexts.contains(tag) {
+                '@'
+            } else {
+                '+'
+            };
+            write!(f, " {}{}", sigil, tag)?;
+        }
+        if let Some(due) = &self.due_date {
+            write!(f, " due:{}", due)?;
+        }
+        if let Some(threshold) = &self.threshold {
+            write!(f, " t:{}", threshold)?;
+        }
+        match self.priority {
+            Some(priority) if self.completed => write!(f, " pri:{}", priority),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_priority(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() == 3 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && bytes[2] == b')'
+}
+
+fn is_date(word: &str) -> bool {
+    NaiveDate::parse_from_str(word, date::FORMAT).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display_round_trip() {
+        for line in [
+            "(A) 2022-09-01 call mom +family @phone due:2022-09-05",
+            "x 2022-09-02 2022-09-01 pay rent +home pri:B",
+            "x 2022-09-02 done on a day",
+            "x finished",
+            "2022-09-01 plain",
+            "(C) review rec:1w",
+            "(B) 2022-09-01 water plants due:2022-09-05 t:2022-09-03",
+        ] {
+            let task = Task::parse(line).unwrap();
+            assert_eq!(task.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn parses_fields() {
+        let task = Task::parse("x 2022-09-02 2022-09-01 pay rent +Home @bank due:2022-09-05 pri:B")
+            .unwrap();
+        assert!(task.completed);
+        assert_eq!(task.completed_on.as_deref(), Some("2022-09-02"));
+        assert_eq!(task.created_on.as_deref(), Some("2022-09-01"));
+        assert_eq!(task.date(), Some("2022-09-01"));
+        assert_eq!(task.text, "pay rent");
+        assert_eq!(task.tags, ["home", "bank"]);
+        assert_eq!(task.contexts, ["bank"]);
+        assert_eq!(task.due_date.as_deref(), Some("2022-09-05"));
+        assert_eq!(task.threshold, None);
+        assert!(task.priority() == Priority::Medium);
+
+        let later = Task::parse("water plants t:2022-09-03 t:soon").unwrap();
+        assert_eq!(later.threshold.as_deref(), Some("2022-09-03"));
+        assert_eq!(later.text, "water plants t:soon");
+
+        let done = Task::parse("x 2022-09-02 only a done date").unwrap();
+        assert_eq!(done.created_on, None);
+        assert_eq!(done.date(), Some("2022-09-02"));
+    }
+
+    #[test]
+    fn rejects_lines_without_text() {
+        for line in [
+            "",
+            "   ",
+            "+project @context",
+            "x 2022-09-02",
+            "(A) due:2022-09-05",
+        ] {
+            assert!(Task::parse(line).is_none(), "{:?}", line);
+        }
+    }
+}
@@ -1,38 +1,102 @@
-use crate::{App, Screen};
+pub mod input;
+pub mod text_area;
+
+use crate::{
+    database::{Day, EntryKind, Hit, Priority},
+    date, App, ProjectFocus,
+};
+use input::TextInput;
+use text_area::TextArea;
 use tui::{
     backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Style},
-    text::Spans,
+    style::{Modifier, Style},
+    text::{Span, Spans},
     widgets::{BarChart, Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
     Frame,
 };
-use unicode_width::UnicodeWidthStr;
 
-pub fn todos_screen<B: Backend>(app: &App, f: &mut Frame<B>, todos: bool) {
+#[derive(Clone, Copy)]
+pub enum Focus<'a> {
+    None,
+    Todos,
+    Notes,
+    EditNotes(&'a TextArea),
+}
+
+pub fn todos_screen<B: Backend>(app: &App, f: &mut Frame<B>, focus: Focus) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .margin(1)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .constraints(
+            [
+                Constraint::Percentage(app.config.layout.todos_width),
+                Constraint::Percentage(100 - app.config.layout.todos_width),
+            ]
+            .as_ref(),
+        )
         .split(f.size());
-    todos_block(app, f, todos, chunks[0]);
-    let active_notes = !todos
-        && !matches!(
-            app.screen,
-            Screen::NewTodo | Screen::DailyTodos | Screen::NewDailyTodo
-        );
-    notes_block(app, f, &app.day.notes, active_notes, chunks[1]);
+    todos_block(app, f, matches!(focus, Focus::Todos), chunks[0]);
+    match focus {
+        Focus::EditNotes(editor) => notes_editor(app, f, editor, chunks[1]),
+        _ => notes_block(
+            app,
+            f,
+            &app.day.notes,
+            matches!(focus, Focus::Notes),
+            chunks[1],
+        ),
+    }
 }
 
 fn todos_block<B: Backend>(app: &App, f: &mut Frame<B>, active: bool, area: Rect) {
+    let area = if app.due.is_empty() {
+        area
+    } else {
+        let height = (app.due.len() as u16 + 2).min(area.height / 2);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(height)].as_ref())
+            .split(area);
+        due_block(app, f, chunks[1]);
+        chunks[0]
+    };
+    let mut title = format!("TODOs | {}", app.day.date);
+    if let Some(tag) = &app.stats_list.filter {
+        title.push_str(&format!(" | #{}", tag));
+    }
+    if app.sort_by_priority {
+        title.push_str(" | by priority");
+    }
     let block = Block::default()
-        .title(format!("TODOs | {}", app.day.date))
+        .title(title)
         .borders(Borders::ALL)
-        .style(Style::default().fg(if active { Color::Yellow } else { Color::White }));
+        .style(border_style(app, active));
     let list = List::new(get_todos_list(app, active)).block(block);
     f.render_widget(list, area);
 }
 
+fn due_block<B: Backend>(app: &App, f: &mut Frame<B>, area: Rect) {
+    let today = date::today();
+    let overdue = app.due.iter().filter(|t| t.is_overdue(&today)).count();
+    let items: Vec<ListItem> = app
+        .due
+        .iter()
+        .map(|todo| {
+            ListItem::new(todo.get_text()).style(Style::default().fg(if todo.is_overdue(&today) {
+                app.config.theme.error.0
+            } else {
+                app.config.theme.text.0
+            }))
+        })
+        .collect();
+    let block = Block::default()
+        .title(format!("Due | {} overdue", overdue))
+        .borders(Borders::ALL)
+        .style(border_style(app, false));
+    f.render_widget(List::new(items).block(block), area);
+}
+
 fn notes_block<B: Backend>(app: &App, f: &mut Frame<B>, text: &str, active: bool, area: Rect) {
     let text = String::from(text);
     let text: Vec<Spans> = text
@@ -40,50 +104,166 @@ fn notes_block<B: Backend>(app: &App, f: &mut Frame<B>, text: &str, active: bool
         .map(|s| Spans::from(s.trim_start()))
         .collect();
     let block = Block::default()
-        .title(format!(
-            "Notes{}",
-            if matches!(app.screen, Screen::EditNotes) {
-                "*"
-            } else {
-                ""
-            }
-        ))
+        .title("Notes")
         .borders(Borders::ALL)
-        .style(Style::default().fg(if active { Color::Yellow } else { Color::White }));
-    let text_block = Paragraph::new(text.clone())
+        .style(border_style(app, active));
+    let text_block = Paragraph::new(text)
         .wrap(Wrap { trim: false })
-        .style(Style::default().fg(Color::White))
+        .style(Style::default().fg(app.config.theme.text.0))
         .block(block);
     f.render_widget(text_block, area);
-    if matches!(app.screen, Screen::EditNotes) {
-        let x = if !text.is_empty() {
-            area.x + 1 + text.last().unwrap().width() as u16
-        } else {
-            area.x + 1
-        };
-        let y = if !text.is_empty() {
-            area.y + text.len() as u16
-        } else {
-            area.y
-        };
-        f.set_cursor(x, y);
-    }
+}
+
+fn notes_editor<B: Backend>(app: &App, f: &mut Frame<B>, editor: &TextArea, area: Rect) {
+    let block = Block::default()
+        .title("Notes*")
+        .borders(Borders::ALL)
+        .style(border_style(app, true));
+    let inner = block.inner(area);
+    let (lines, (x, y)) = editor.view(inner.width as usize, inner.height as usize);
+    let text: Vec<Spans> = lines.into_iter().map(Spans::from).collect();
+    let text_block = Paragraph::new(text)
+        .style(Style::default().fg(app.config.theme.text.0))
+        .block(block);
+    f.render_widget(text_block, area);
+    f.set_cursor(inner.x + x as u16, inner.y + y as u16);
 }
 
 pub fn daily_todos_screen<B: Backend>(app: &App, f: &mut Frame<B>, active: bool) {
     let block = List::new(get_daily_todos_list(app, active))
-        .style(Style::default().fg(Color::White))
+        .style(Style::default().fg(app.config.theme.text.0))
         .block(
             Block::default()
                 .title("Daily TODOs")
                 .borders(Borders::ALL)
-                .style(Style::default().fg(if active { Color::Yellow } else { Color::White })),
+                .style(border_style(app, active)),
         );
-    let area = centered_rect(30, 50, f.size());
+    let area = centered_rect(app.config.layout.todos_width, 50, f.size());
     f.render_widget(Clear, area);
     f.render_widget(block, area);
 }
 
+pub fn projects_screen<B: Backend>(app: &App, f: &mut Frame<B>, active: bool) {
+    let area = centered_rect(app.config.layout.popup_width, 60, f.size());
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .split(area);
+    let projects = &app.projects;
+    let style = |selected: bool, focus: ProjectFocus| {
+        Style::default().fg(if selected && active && projects.focus == focus {
+            app.config.theme.active.0
+        } else {
+            app.config.theme.text.0
+        })
+    };
+    let names: Vec<ListItem> = projects
+        .list
+        .iter()
+        .enumerate()
+        .map(|(index, project)| {
+            let done = project.todos.iter().filter(|t| t.completed).count();
+            ListItem::new(format!("{} {}/{}", project.name, done, project.todos.len()))
+                .style(style(index == projects.index, ProjectFocus::Projects))
+        })
+        .collect();
+    let names = List::new(names).block(
+        Block::default()
+            .title("Projects")
+            .borders(Borders::ALL)
+            .style(border_style(
+                app,
+                active && projects.focus == ProjectFocus::Projects,
+            )),
+    );
+    let (title, todos) = match projects.list.get(projects.index) {
+        Some(project) => (
+            format!("{} | Enter: move to {}", project.name, app.day.date),
+            project
+                .todos
+                .iter()
+                .enumerate()
+                .map(|(index, todo)| {
+                    ListItem::new(todo.get_text())
+                        .style(style(index == projects.todo_index, ProjectFocus::Todos))
+                })
+                .collect(),
+        ),
+        None => (String::from("TODOs"), Vec::new()),
+    };
+    let todos = List::new(todos).block(Block::default().title(title).borders(Borders::ALL).style(
+        border_style(app, active && projects.focus == ProjectFocus::Todos),
+    ));
+    f.render_widget(Clear, area);
+    f.render_widget(names, chunks[0]);
+    f.render_widget(todos, chunks[1]);
+}
+
+pub fn carry_over_screen<B: Backend>(
+    app: &App,
+    f: &mut Frame<B>,
+    from: &Day,
+    selected: &[bool],
+    index: usize,
+) {
+    let items: Vec<ListItem> = from
+        .unfinished()
+        .zip(selected)
+        .enumerate()
+        .map(|(i, (todo, selected))| {
+            let mark = if *selected { "[>]" } else { "[ ]" };
+            ListItem::new(format!("{} {}", mark, todo.text)).style(Style::default().fg(
+                if i == index {
+                    app.config.theme.active.0
+                } else {
+                    app.config.theme.text.0
+                },
+            ))
+        })
+        .collect();
+    let list = List::new(items).block(
+        Block::default()
+            .title(format!(
+                "Carry over from {} | Enter: carry, Esc: leave",
+                from.date
+            ))
+            .borders(Borders::ALL)
+            .style(border_style(app, true)),
+    );
+    let area = centered_rect(app.config.layout.popup_width, 50, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
+pub fn picker_screen<B: Backend>(
+    app: &App,
+    f: &mut Frame<B>,
+    title: &str,
+    items: Vec<String>,
+    index: usize,
+) {
+    let items: Vec<ListItem> = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            ListItem::new(item).style(Style::default().fg(if i == index {
+                app.config.theme.active.0
+            } else {
+                app.config.theme.text.0
+            }))
+        })
+        .collect();
+    let list = List::new(items).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .style(border_style(app, true)),
+    );
+    let area = centered_rect(app.config.layout.popup_width, 50, f.size());
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+}
+
 pub fn stats_screen<B: Backend>(app: &App, f: &mut Frame<B>) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -92,12 +272,21 @@ pub fn stats_screen<B: Backend>(app: &App, f: &mut Frame<B>) {
         .split(f.size());
     let chunks2 = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .constraints(
+            [
+                Constraint::Percentage(app.config.layout.todos_width),
+                Constraint::Percentage(100 - app.config.layout.todos_width),
+            ]
+            .as_ref(),
+        )
         .split(chunks[1]);
     let block = Block::default()
-        .title("Days")
+        .title(match &app.stats_list.filter {
+            Some(tag) => format!("Days | #{}", tag),
+            None => String::from("Days"),
+        })
         .borders(Borders::ALL)
-        .style(Style::default().fg(Color::Yellow));
+        .style(border_style(app, true));
     let data: Vec<(String, u64)> = app
         .stats_list
         .list
@@ -114,82 +303,185 @@ pub fn stats_screen<B: Backend>(app: &App, f: &mut Frame<B>) {
             )
         })
         .collect();
-    let data: Vec<(&str, u64)> = data.iter().map(|d| (d.0.as_str(), d.1 as u64)).collect();
+    let data: Vec<(&str, u64)> = data.iter().map(|d| (d.0.as_str(), d.1)).collect();
     let chart = BarChart::default()
         .bar_width(12)
-        .bar_style(Style::default().fg(Color::Yellow))
-        .value_style(Style::default().fg(Color::White).bg(Color::Yellow))
-        .label_style(Style::default().fg(Color::White))
+        .bar_style(border_style(app, true))
+        .value_style(
+            Style::default()
+                .fg(app.config.theme.text.0)
+                .bg(app.config.theme.active.0),
+        )
+        .label_style(Style::default().fg(app.config.theme.text.0))
         .data(&data)
         .block(block);
     f.render_widget(chart, chunks[0]);
-    let current_day = app.stats_list.get_current(&app.db).unwrap();
-    let todos_list: Vec<ListItem> = current_day
-        .todos
-        .iter()
-        .map(|todo| ListItem::new(todo.get_text()))
-        .collect();
+    let (date, todos_list, notes) = match &app.stats_list.current {
+        Some(day) => (
+            day.date.as_str(),
+            day.display_order(false, app.stats_list.filter.as_deref())
+                .into_iter()
+                .map(|index| match day.todos[index].deferred_days(&day.date) {
+                    Some(days) if days > 0 => {
+                        ListItem::new(format!("{} (deferred {}d)", todo_line(day, index), days))
+                    }
+                    _ => ListItem::new(todo_line(day, index)),
+                })
+                .collect(),
+            day.notes.as_str(),
+        ),
+        None => ("0000-00-00", vec![], ""),
+    };
     let todos_block = List::new(todos_list).block(
         Block::default()
-            .title(format!("TODOs | {}", current_day.date))
+            .title(format!("TODOs | {}", date))
             .borders(Borders::ALL)
-            .style(Style::default().fg(Color::White)),
+            .style(border_style(app, false)),
     );
     f.render_widget(todos_block, chunks2[0]);
-    notes_block(app, f, &current_day.notes, false, chunks2[1]);
+    notes_block(app, f, notes, false, chunks2[1]);
 }
 
-pub fn new_daily_todo_screen<B: Backend>(app: &App, f: &mut Frame<B>) {
-    let block = Paragraph::new(app.daily_todos.input.as_ref())
-        .style(Style::default().fg(Color::White))
+pub fn new_todo_screen<B: Backend>(app: &App, f: &mut Frame<B>, title: &str, input: &TextInput) {
+    let area = centered_input(app.config.layout.popup_width, f.size());
+    let cursor = input.cursor_width() as u16;
+    let scroll = cursor.saturating_sub(area.width.saturating_sub(3));
+    let block = Paragraph::new(input.value())
+        .scroll((0, scroll))
+        .style(Style::default().fg(app.config.theme.text.0))
         .block(
             Block::default()
-                .title("New Daily TODO")
+                .title(title)
                 .borders(Borders::ALL)
-                .style(Style::default().fg(Color::Yellow)),
+                .style(border_style(app, true)),
         );
-    let area = centered_input(60, f.size());
     f.render_widget(Clear, area);
     f.render_widget(block, area);
-    f.set_cursor(
-        area.x + app.daily_todos.input.width() as u16 + 1,
-        area.y + 1,
+    f.set_cursor(area.x + 1 + cursor - scroll, area.y + 1);
+}
+
+pub fn search_screen<B: Backend>(
+    app: &App,
+    f: &mut Frame<B>,
+    title: &str,
+    input: &TextInput,
+    results: &[Hit],
+    index: usize,
+) {
+    let area = centered_rect(app.config.layout.popup_width, 60, f.size());
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(area);
+    let highlight = Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let items: Vec<ListItem> = results
+        .iter()
+        .enumerate()
+        .map(|(i, hit)| {
+            let mark = match hit.kind {
+                EntryKind::Todo { completed: true } => "[x]",
+                EntryKind::Todo { completed: false } => "[ ]",
+                EntryKind::Note => " ~ ",
+            };
+            let mut spans = vec![Span::raw(format!("{} {} ", hit.date, mark))];
+            spans.extend(hit.snippet.iter().map(|(part, matched)| {
+                if *matched {
+                    Span::styled(part.as_str(), highlight)
+                } else {
+                    Span::raw(part.as_str())
+                }
+            }));
+            ListItem::new(Spans::from(spans)).style(Style::default().fg(if i == index {
+                app.config.theme.active.0
+            } else {
+                app.config.theme.text.0
+            }))
+        })
+        .collect();
+    let list = List::new(items).block(
+        Block::default()
+            .title(format!("{} results | Enter: open day", results.len()))
+            .borders(Borders::ALL)
+            .style(border_style(app, false)),
     );
+    let cursor = input.cursor_width() as u16;
+    let scroll = cursor.saturating_sub(chunks[0].width.saturating_sub(3));
+    let query = Paragraph::new(input.value())
+        .scroll((0, scroll))
+        .style(Style::default().fg(app.config.theme.text.0))
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .style(border_style(app, true)),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(query, chunks[0]);
+    f.render_widget(list, chunks[1]);
+    f.set_cursor(chunks[0].x + 1 + cursor - scroll, chunks[0].y + 1);
 }
 
-pub fn new_todo_screen<B: Backend>(app: &App, f: &mut Frame<B>) {
-    let block = Paragraph::new(app.input.as_ref())
-        .style(Style::default().fg(Color::White))
+pub fn confirm_screen<B: Backend>(app: &App, f: &mut Frame<B>, title: &str, text: &str) {
+    let area = centered_input(app.config.layout.popup_width, f.size());
+    let block = Paragraph::new(text)
+        .style(Style::default().fg(app.config.theme.text.0))
         .block(
             Block::default()
-                .title("New TODO")
+                .title(title)
                 .borders(Borders::ALL)
-                .style(Style::default().fg(Color::Yellow)),
+                .style(border_style(app, true)),
         );
-    let area = centered_input(60, f.size());
     f.render_widget(Clear, area);
     f.render_widget(block, area);
-    f.set_cursor(area.x + app.input.width() as u16 + 1, area.y + 1);
 }
 
-pub fn get_todos_list(app: &App, active: bool) -> Vec<ListItem> {
+/// A todo indented under its parent, with a fold marker and the completed
+/// sub-task count when it has sub-tasks.
+fn todo_line(day: &Day, index: usize) -> String {
+    let todo = &day.todos[index];
+    let indent = "  ".repeat(day.depth(index));
+    if !day.has_children(index) {
+        return format!("{}{}", indent, todo.get_text());
+    }
+    let (done, total) = day.progress(index);
+    let marker = if todo.collapsed { '+' } else { '-' };
+    format!(
+        "{}{} {} {}/{}",
+        indent,
+        marker,
+        todo.get_text(),
+        done,
+        total
+    )
+}
+
+pub fn get_todos_list(app: &App, active: bool) -> Vec<ListItem<'_>> {
+    let today = date::today();
+    let theme = &app.config.theme;
     app.day
-        .todos
-        .iter()
-        .enumerate()
-        .map(|(index, todo)| {
-            ListItem::new(todo.get_text()).style(Style::default().fg(
-                if index == app.index && active {
-                    Color::Yellow
-                } else {
-                    Color::White
-                },
-            ))
+        .display_order(app.sort_by_priority, app.stats_list.filter.as_deref())
+        .into_iter()
+        .map(|index| {
+            let todo = &app.day.todos[index];
+            let color = if index == app.index && active {
+                theme.active.0
+            } else if todo.is_overdue(&today) {
+                theme.error.0
+            } else {
+                match todo.priority {
+                    _ if todo.completed => theme.text.0,
+                    Priority::High => theme.high.0,
+                    Priority::Medium => theme.medium.0,
+                    Priority::Low => theme.low.0,
+                    Priority::None => theme.text.0,
+                }
+            };
+            ListItem::new(todo_line(&app.day, index)).style(Style::default().fg(color))
         })
         .collect()
 }
 
-pub fn get_daily_todos_list(app: &App, active: bool) -> Vec<ListItem> {
+pub fn get_daily_todos_list(app: &App, active: bool) -> Vec<ListItem<'_>> {
     app.daily_todos
         .list
         .iter()
@@ -197,15 +489,37 @@ pub fn get_daily_todos_list(app: &App, active: bool) -> Vec<ListItem> {
         .map(|(index, todo)| {
             ListItem::new(todo.get_text()).style(Style::default().fg(
                 if index == app.daily_todos.index && active {
-                    Color::Yellow
+                    app.config.theme.active.0
                 } else {
-                    Color::White
+                    app.config.theme.text.0
                 },
             ))
         })
         .collect()
 }
 
+pub fn status_bar<B: Backend>(app: &App, f: &mut Frame<B>) {
+    if let Some(status) = &app.status {
+        let size = f.size();
+        let area = Rect::new(size.x, size.bottom().saturating_sub(1), size.width, 1);
+        let text = Paragraph::new(format!(" {}", status)).style(
+            Style::default()
+                .fg(app.config.theme.text.0)
+                .bg(app.config.theme.error.0),
+        );
+        f.render_widget(Clear, area);
+        f.render_widget(text, area);
+    }
+}
+
+fn border_style(app: &App, active: bool) -> Style {
+    Style::default().fg(if active {
+        app.config.theme.active.0
+    } else {
+        app.config.theme.inactive.0
+    })
+}
+
 pub fn centered_input(percent_x: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
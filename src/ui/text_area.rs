@@ -0,0 +1,329 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::cell::Cell;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const UNDO_LIMIT: usize = 200;
+
+#[derive(Clone)]
+struct Snapshot {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+}
+
+// Multi-line editor, `col` is a byte offset on a char boundary of `lines[row]`.
+pub struct TextArea {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+    scroll: Cell<usize>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    typing: bool,
+}
+
+// Splits a line into byte ranges that fit into `width` columns.
+fn wrap(line: &str, width: usize) -> Vec<(usize, usize)> {
+    let mut ranges = vec![];
+    let mut start = 0;
+    let mut used = 0;
+    for (i, c) in line.char_indices() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width && i > start {
+            ranges.push((start, i));
+            start = i;
+            used = 0;
+        }
+        used += w;
+    }
+    ranges.push((start, line.len()));
+    ranges
+}
+
+impl TextArea {
+    pub fn new(text: &str) -> Self {
+        let lines: Vec<String> = text.split('\n').map(String::from).collect();
+        let row = lines.len() - 1;
+        let col = lines[row].len();
+        Self {
+            lines,
+            row,
+            col,
+            scroll: Cell::new(0),
+            undo: vec![],
+            redo: vec![],
+            typing: false,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            lines: self.lines.clone(),
+            row: self.row,
+            col: self.col,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lines = snapshot.lines;
+        self.row = snapshot.row;
+        self.col = snapshot.col;
+    }
+
+    // Records the state before an edit; a run of typed characters is one step.
+    fn record(&mut self, typing: bool) {
+        if !(typing && self.typing) {
+            self.undo.push(self.snapshot());
+            if self.undo.len() > UNDO_LIMIT {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+        }
+        self.typing = typing;
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(snapshot) = self.undo.pop() {
+            self.redo.push(self.snapshot());
+            self.restore(snapshot);
+        }
+        self.typing = false;
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(snapshot) = self.redo.pop() {
+            self.undo.push(self.snapshot());
+            self.restore(snapshot);
+        }
+        self.typing = false;
+    }
+
+    fn line(&self) -> &str {
+        &self.lines[self.row]
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.line()[..self.col]
+            .chars()
+            .next_back()
+            .map_or(self.col, |c| self.col - c.len_utf8())
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.line()[self.col..]
+            .chars()
+            .next()
+            .map_or(self.col, |c| self.col + c.len_utf8())
+    }
+
+    // Byte offset in `line` closest to the display column `width`.
+    fn offset_at_width(line: &str, width: usize) -> usize {
+        let mut used = 0;
+        for (i, c) in line.char_indices() {
+            used += c.width().unwrap_or(0);
+            if used > width {
+                return i;
+            }
+        }
+        line.len()
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.record(!c.is_whitespace());
+        let col = self.col;
+        self.lines[self.row].insert(col, c);
+        self.col += c.len_utf8();
+    }
+
+    pub fn insert_str(&mut self, text: &str) {
+        self.record(false);
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let tail = self.lines[self.row].split_off(self.col);
+        let mut parts = text.split('\n');
+        if let Some(first) = parts.next() {
+            self.lines[self.row].push_str(first);
+        }
+        for part in parts {
+            self.row += 1;
+            self.lines.insert(self.row, part.to_string());
+        }
+        self.col = self.lines[self.row].len();
+        self.lines[self.row].push_str(&tail);
+    }
+
+    pub fn new_line(&mut self) {
+        self.record(false);
+        let tail = self.lines[self.row].split_off(self.col);
+        self.row += 1;
+        self.col = 0;
+        self.lines.insert(self.row, tail);
+    }
+
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            self.record(false);
+            let start = self.previous_boundary();
+            let end = self.col;
+            self.lines[self.row].replace_range(start..end, "");
+            self.col = start;
+        } else if self.row > 0 {
+            self.record(false);
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.lines[self.row].len();
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.col < self.line().len() {
+            self.record(false);
+            let end = self.next_boundary();
+            let start = self.col;
+            self.lines[self.row].replace_range(start..end, "");
+        } else if self.row + 1 < self.lines.len() {
+            self.record(false);
+            let line = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn delete_word(&mut self) {
+        if self.col == 0 {
+            return self.backspace();
+        }
+        self.record(false);
+        let start = self.line()[..self.col]
+            .trim_end()
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let end = self.col;
+        self.lines[self.row].replace_range(start..end, "");
+        self.col = start;
+    }
+
+    pub fn delete_to_start(&mut self) {
+        self.record(false);
+        let end = self.col;
+        self.lines[self.row].replace_range(..end, "");
+        self.col = 0;
+    }
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col = self.previous_boundary();
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line().len();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if self.col < self.line().len() {
+            self.col = self.next_boundary();
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    fn move_to_row(&mut self, row: usize) {
+        let width = self.line()[..self.col].width();
+        self.row = row;
+        self.col = Self::offset_at_width(self.line(), width);
+    }
+
+    pub fn up(&mut self) {
+        if self.row > 0 {
+            self.move_to_row(self.row - 1);
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.move_to_row(self.row + 1);
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.col = self.line().len();
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let moving = !matches!(key.code, KeyCode::Char(_));
+        match key.code {
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            KeyCode::Char('w') if ctrl => self.delete_word(),
+            KeyCode::Char('u') if ctrl => self.delete_to_start(),
+            KeyCode::Char(_) if ctrl => return false,
+            KeyCode::Char(c) => self.insert(c),
+            KeyCode::Enter => self.new_line(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.left(),
+            KeyCode::Right => self.right(),
+            KeyCode::Up => self.up(),
+            KeyCode::Down => self.down(),
+            KeyCode::Home => self.home(),
+            KeyCode::End => self.end(),
+            _ => return false,
+        }
+        if moving {
+            self.typing = false;
+        }
+        true
+    }
+
+    // Wrapped lines visible in a `width` x `height` area, and the cursor
+    // position relative to that area. Scrolls to keep the cursor visible.
+    pub fn view(&self, width: usize, height: usize) -> (Vec<String>, (usize, usize)) {
+        let width = width.max(1);
+        let mut rows = vec![];
+        let mut cursor = (0, 0);
+        for (index, line) in self.lines.iter().enumerate() {
+            let ranges = wrap(line, width);
+            if index == self.row {
+                let (segment, (start, _)) = ranges
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find(|(_, (start, _))| *start <= self.col)
+                    .map(|(i, r)| (i, *r))
+                    .unwrap_or((0, (0, 0)));
+                let x = line[start..self.col].width();
+                cursor = if x >= width {
+                    (0, rows.len() + segment + 1)
+                } else {
+                    (x, rows.len() + segment)
+                };
+            }
+            rows.extend(
+                ranges
+                    .iter()
+                    .map(|(start, end)| line[*start..*end].to_string()),
+            );
+        }
+        let height = height.max(1);
+        let mut scroll = self.scroll.get();
+        if cursor.1 < scroll {
+            scroll = cursor.1;
+        } else if cursor.1 >= scroll + height {
+            scroll = cursor.1 + 1 - height;
+        }
+        self.scroll.set(scroll);
+        let visible = rows.into_iter().skip(scroll).take(height).collect();
+        (visible, (cursor.0, cursor.1 - scroll))
+    }
+}